@@ -0,0 +1,225 @@
+use alloy_primitives::{Address, I256, U256};
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::full_math::mul_div;
+use crate::libraries::sqrt_price_math::Q96;
+use crate::quoter::{HopQuote, QuoteError, hop_quote};
+use crate::router::fee_factor;
+
+/// The input the search starts from, doubled or halved towards the most profitable one.
+const START_AMOUNT_IN: U256 = U256::from_limbs([1 << 20, 0, 0, 0]);
+const MAX_CYCLE_LENGTH: usize = 3;
+
+/// A profitable cycle and the input that maximizes its profit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arbitrage {
+    /// The tokens along the cycle, starting and ending with the token the profit is made in.
+    pub tokens: Vec<Address>,
+    /// The pools along the cycle.
+    pub pools: Vec<Address>,
+    /// Square root of the product of the spot prices of the hops after fees, as a Q96. Above
+    /// `1 << 96` for every reported cycle.
+    pub sqrt_price_product_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub profit: U256,
+    pub hops: Vec<HopQuote>,
+}
+
+/// A cycle that couldn't be priced or sized, e.g. because one of its pools has no price yet.
+#[derive(Debug)]
+pub struct FailedCycle {
+    /// The token the cycle starts and ends with.
+    pub token: Address,
+    pub pools: Vec<Address>,
+    pub error: QuoteError,
+}
+
+#[derive(Debug, Default)]
+pub struct ArbitrageScan {
+    pub found: Vec<Arbitrage>,
+    pub failed: Vec<FailedCycle>,
+}
+
+/// Finds the 2 and 3 pool cycles among `pools` whose spot prices multiply to more than 1 after
+/// fees, and sizes each by searching for the input with the largest profit over simulated
+/// swaps. Each cycle is reported once, starting from the first of its pools in `pools`, in
+/// the token that pool is entered with. Cycles that can't be traded at a profit aren't
+/// reported, and cycles that fail to quote are reported in `ArbitrageScan::failed` without
+/// holding up the others.
+pub fn find_arbitrage(pools: &[&UniswapV3Pool]) -> ArbitrageScan {
+    let mut scan = ArbitrageScan::default();
+    for (first, pool) in pools.iter().enumerate() {
+        for token in [pool.token0, pool.token1] {
+            let mut cycle = vec![first];
+            search_cycles(pools, token, token, &mut cycle, &mut scan);
+        }
+    }
+    scan
+}
+
+// extends `cycle`, ending in `token`, with the pools after its first one
+fn search_cycles(
+    pools: &[&UniswapV3Pool],
+    start: Address,
+    token: Address,
+    cycle: &mut Vec<usize>,
+    scan: &mut ArbitrageScan,
+) {
+    let last = pools[*cycle.last().unwrap()];
+    let token = other_token(last, token);
+    if token == start && cycle.len() > 1 {
+        let hops: Vec<_> = cycle.iter().map(|&i| pools[i]).collect();
+        match size_cycle(&hops, start) {
+            Ok(Some(arbitrage)) => scan.found.push(arbitrage),
+            Ok(None) => {}
+            Err(error) => scan.failed.push(FailedCycle {
+                token: start,
+                pools: hops.iter().map(|pool| pool.metadata.address).collect(),
+                error,
+            }),
+        }
+        return;
+    }
+    if cycle.len() == MAX_CYCLE_LENGTH {
+        return;
+    }
+
+    for next in cycle[0] + 1..pools.len() {
+        let pool = pools[next];
+        if cycle.contains(&next) || (pool.token0 != token && pool.token1 != token) {
+            continue;
+        }
+        cycle.push(next);
+        search_cycles(pools, start, token, cycle, scan);
+        cycle.pop();
+    }
+}
+
+fn other_token(pool: &UniswapV3Pool, token: Address) -> Address {
+    if token == pool.token0 {
+        pool.token1
+    } else {
+        pool.token0
+    }
+}
+
+fn size_cycle(pools: &[&UniswapV3Pool], start: Address) -> Result<Option<Arbitrage>, QuoteError> {
+    let mut tokens = vec![start];
+    let mut sqrt_price_product_x96 = Q96;
+    for pool in pools {
+        let token_in = *tokens.last().unwrap();
+        let sqrt_price_x96 = sqrt_spot_price_after_fee(pool, token_in == pool.token0)?;
+        sqrt_price_product_x96 = mul_div(sqrt_price_product_x96, sqrt_price_x96, Q96)?;
+        tokens.push(other_token(pool, token_in));
+    }
+    if sqrt_price_product_x96 <= Q96 {
+        return Ok(None);
+    }
+
+    let profit = |amount_in: U256| -> Result<(I256, Vec<HopQuote>), QuoteError> {
+        let hops = quote_cycle(pools, &tokens, amount_in)?;
+        let amount_out = hops.last().map_or(U256::ZERO, |hop| hop.amount_out);
+        Ok((
+            I256::from_raw(amount_out).saturating_sub(I256::from_raw(amount_in)),
+            hops,
+        ))
+    };
+
+    // the profit is concave in the input, so double the input while the profit grows, or halve
+    // it if it doesn't, and then narrow down the range around the last step
+    let max_amount_in = I256::MAX.into_raw() >> 1;
+    let mut amount_in = START_AMOUNT_IN;
+    let mut best = profit(amount_in)?.0;
+    while amount_in < max_amount_in {
+        let next = profit(amount_in << 1)?.0;
+        if next < best {
+            break;
+        }
+        amount_in <<= 1;
+        best = next;
+    }
+    if amount_in == START_AMOUNT_IN {
+        while amount_in > U256::from(1) {
+            let next = profit(amount_in >> 1usize)?.0;
+            if next <= best {
+                break;
+            }
+            amount_in >>= 1usize;
+            best = next;
+        }
+    }
+
+    let (mut low, mut high) = ((amount_in >> 1usize).max(U256::from(1)), amount_in << 1);
+    while high - low > U256::from(2) {
+        let third = (high - low) / U256::from(3);
+        let (left, right) = (low + third, high - third);
+        if profit(left)?.0 < profit(right)?.0 {
+            low = left;
+        } else {
+            high = right;
+        }
+    }
+
+    let mut best: Option<(U256, I256, Vec<HopQuote>)> = None;
+    let mut amount_in = low;
+    while amount_in <= high {
+        let (profit, hops) = profit(amount_in)?;
+        if best.as_ref().is_none_or(|(_, best, _)| profit > *best) {
+            best = Some((amount_in, profit, hops));
+        }
+        amount_in += U256::from(1);
+    }
+
+    let (amount_in, profit, hops) = best.unwrap();
+    if profit <= I256::ZERO {
+        return Ok(None);
+    }
+    Ok(Some(Arbitrage {
+        tokens,
+        pools: pools.iter().map(|pool| pool.metadata.address).collect(),
+        sqrt_price_product_x96,
+        amount_in,
+        amount_out: hops.last().unwrap().amount_out,
+        profit: profit.into_raw(),
+        hops,
+    }))
+}
+
+/// Quotes the hops of a cycle one after the other, stopping early if a hop yields nothing.
+fn quote_cycle(
+    pools: &[&UniswapV3Pool],
+    tokens: &[Address],
+    amount_in: U256,
+) -> Result<Vec<HopQuote>, QuoteError> {
+    let mut amount = amount_in;
+    let mut hops = Vec::with_capacity(pools.len());
+    for (i, pool) in pools.iter().enumerate() {
+        if amount == U256::ZERO {
+            return Ok(Vec::new());
+        }
+        let (token_in, token_out) = (tokens[i], tokens[i + 1]);
+        let amount_in = I256::try_from(amount).map_err(|_| QuoteError::AmountTooLarge(amount))?;
+        let result = pool.quote_exact_input(token_in == pool.token0, amount_in)?;
+        let hop = hop_quote(pool, token_in, token_out, &result);
+        amount = hop.amount_out;
+        hops.push(hop);
+    }
+    Ok(hops)
+}
+
+/// Square root of the output per unit of input at the current price of `pool`, after the fee,
+/// as a Q96. Squaring it as a Q96 would truncate small prices to nothing, so cycles are priced
+/// by their square roots, like `router.rs` compares pools.
+fn sqrt_spot_price_after_fee(
+    pool: &UniswapV3Pool,
+    zero_for_one: bool,
+) -> Result<U256, UniswapV3MathError> {
+    let sqrt_price_x96 = pool.slot0.sqrt_price_x96;
+    if zero_for_one {
+        mul_div(sqrt_price_x96, fee_factor(pool), Q96)
+    } else {
+        mul_div(fee_factor(pool), Q96, sqrt_price_x96)
+    }
+}
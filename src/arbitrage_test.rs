@@ -0,0 +1,225 @@
+use super::*;
+use crate::arbitrage::find_arbitrage;
+use crate::libraries::error::UniswapV3MathError;
+use crate::quoter::QuoteError;
+use crate::test_utils::{SNAPSHOT, owner};
+use std::str::FromStr;
+
+fn load() -> UniswapV3Pool {
+    UniswapV3Pool::from_json_file(SNAPSHOT).unwrap()
+}
+
+/// A copy of the snapshot pool at another address.
+fn copy(pool: &UniswapV3Pool, last_byte: u8) -> UniswapV3Pool {
+    let mut copy = pool.clone();
+    copy.metadata.address = Address::with_last_byte(last_byte);
+    copy
+}
+
+/// The snapshot pool with its liquidity divided by `divisor`, leaving the price as it is.
+fn scaled(pool: &UniswapV3Pool, divisor: u128) -> UniswapV3Pool {
+    let mut scaled = pool.clone();
+    scaled.liquidity /= divisor;
+    for info in scaled.ticks.values_mut() {
+        info.liquidity_gross /= divisor;
+        info.liquidity_net /= divisor as i128;
+    }
+    scaled
+}
+
+fn cycle_output(pools: &[&UniswapV3Pool], tokens: &[Address], amount_in: U256) -> U256 {
+    let mut amount = amount_in;
+    for (i, pool) in pools.iter().enumerate() {
+        let zero_for_one = tokens[i] == pool.token0;
+        let mut swapped = (*pool).clone();
+        let result = swapped
+            .swap(SwapParams::exact_input(
+                owner(),
+                zero_for_one,
+                I256::from_raw(amount),
+            ))
+            .unwrap();
+        amount = if zero_for_one {
+            (-result.amount1).into_raw()
+        } else {
+            (-result.amount0).into_raw()
+        };
+    }
+    amount
+}
+
+#[test]
+fn test_two_pool_cycle() {
+    let pool = load();
+    let mut moved = copy(&pool, 1);
+    moved
+        .swap(SwapParams::exact_input(
+            owner(),
+            true,
+            I256::try_from(10u128.pow(23)).unwrap(),
+        ))
+        .unwrap();
+    let pools = [&pool, &moved];
+
+    let scan = find_arbitrage(&pools);
+    assert!(scan.failed.is_empty());
+    assert_eq!(scan.found.len(), 1);
+    let arbitrage = &scan.found[0];
+    // token0 is dearer in the original pool, so it's sold there and bought back in the copy
+    assert_eq!(
+        arbitrage.tokens,
+        vec![pool.token0, pool.token1, pool.token0]
+    );
+    assert_eq!(
+        arbitrage.pools,
+        vec![pool.metadata.address, moved.metadata.address]
+    );
+    assert!(arbitrage.sqrt_price_product_x96 > U256::from(1) << 96);
+    assert_eq!(arbitrage.profit, arbitrage.amount_out - arbitrage.amount_in);
+    assert_eq!(arbitrage.hops.len(), 2);
+    assert_eq!(arbitrage.hops[1].amount_in, arbitrage.hops[0].amount_out);
+
+    // the reported amounts are those of the swaps, and the profit is the largest nearby
+    assert_eq!(
+        cycle_output(&pools, &arbitrage.tokens, arbitrage.amount_in),
+        arbitrage.amount_out
+    );
+    for amount_in in [
+        arbitrage.amount_in * U256::from(99) / U256::from(100),
+        arbitrage.amount_in * U256::from(101) / U256::from(100),
+    ] {
+        let amount_out = cycle_output(&pools, &arbitrage.tokens, amount_in);
+        assert!(amount_out < amount_in + arbitrage.profit);
+    }
+}
+
+#[test]
+fn test_three_pool_cycle() {
+    // the copies trade token1 and token0 against a third token at the same price, so going
+    // around gains the price of the snapshot pool
+    let pool = load();
+    let third = Address::from_str("0xffffffffffffffffffffffffffffffffffffffff").unwrap();
+    let mut second = copy(&pool, 1);
+    second.token0 = pool.token1;
+    second.token1 = third;
+    let mut closing = copy(&pool, 2);
+    closing.token1 = third;
+    let pools = [&pool, &second, &closing];
+
+    let scan = find_arbitrage(&pools);
+    assert!(scan.failed.is_empty());
+    assert_eq!(scan.found.len(), 1);
+    let arbitrage = &scan.found[0];
+    assert_eq!(
+        arbitrage.tokens,
+        vec![pool.token0, pool.token1, third, pool.token0]
+    );
+    assert_eq!(arbitrage.hops.len(), 3);
+    assert!(arbitrage.profit > U256::ZERO);
+    assert_eq!(
+        cycle_output(&pools, &arbitrage.tokens, arbitrage.amount_in),
+        arbitrage.amount_out
+    );
+}
+
+#[test]
+fn test_no_arbitrage_between_equal_prices() {
+    let pool = load();
+    let other = copy(&pool, 1);
+    assert!(find_arbitrage(&[&pool, &other]).found.is_empty());
+    assert!(find_arbitrage(&[&pool]).found.is_empty());
+}
+
+#[test]
+fn test_small_cycle() {
+    // the cycle of `test_two_pool_cycle` over 10^18 times less liquidity, which is most
+    // profitable well below the input the search starts from
+    let pool = scaled(&load(), 10u128.pow(18));
+    let mut moved = copy(&pool, 1);
+    moved
+        .swap(SwapParams::exact_input(
+            owner(),
+            true,
+            I256::try_from(10u128.pow(5)).unwrap(),
+        ))
+        .unwrap();
+    let pools = [&pool, &moved];
+
+    let scan = find_arbitrage(&pools);
+    assert_eq!(scan.found.len(), 1);
+    let arbitrage = &scan.found[0];
+    assert!(arbitrage.amount_in < U256::from(1 << 19));
+    assert!(arbitrage.profit > U256::ZERO);
+    for amount_in in [
+        arbitrage.amount_in * U256::from(9) / U256::from(10),
+        arbitrage.amount_in * U256::from(11) / U256::from(10),
+    ] {
+        let amount_out = cycle_output(&pools, &arbitrage.tokens, amount_in);
+        assert!(amount_out < amount_in + arbitrage.profit);
+    }
+}
+
+#[test]
+fn test_low_price_cycle() {
+    // token0 is worth about 10^-35 token1, a price that truncates to 0 as a Q96, with only the full
+    // range position in range
+    let mut pool = load();
+    let tick = -800000;
+    pool.slot0.sqrt_price_x96 = tick_math::get_sqrt_ratio_at_tick(tick).unwrap();
+    pool.slot0.tick = tick;
+    pool.liquidity = pool.ticks[&-887272].liquidity_net as u128;
+    let mut moved = copy(&pool, 1);
+    moved
+        .swap(SwapParams::exact_input(
+            owner(),
+            false,
+            I256::try_from(20000).unwrap(),
+        ))
+        .unwrap();
+    let pools = [&pool, &moved];
+
+    let scan = find_arbitrage(&pools);
+    assert!(scan.failed.is_empty());
+    assert_eq!(scan.found.len(), 1);
+    let arbitrage = &scan.found[0];
+    assert!(arbitrage.sqrt_price_product_x96 > U256::from(1) << 96);
+    assert!(arbitrage.profit > U256::ZERO);
+    assert_eq!(
+        cycle_output(&pools, &arbitrage.tokens, arbitrage.amount_in),
+        arbitrage.amount_out
+    );
+}
+
+#[test]
+fn test_failed_cycles_are_collected() {
+    let pool = load();
+    let mut moved = copy(&pool, 1);
+    moved
+        .swap(SwapParams::exact_input(
+            owner(),
+            true,
+            I256::try_from(10u128.pow(23)).unwrap(),
+        ))
+        .unwrap();
+    // a pool that was created but never initialized has no price
+    let mut uninitialized = copy(&pool, 2);
+    uninitialized.slot0.sqrt_price_x96 = U256::ZERO;
+    let pools = [&pool, &moved, &uninitialized];
+
+    let scan = find_arbitrage(&pools);
+    assert_eq!(scan.found.len(), 1);
+    assert_eq!(
+        scan.found[0].pools,
+        vec![pool.metadata.address, moved.metadata.address]
+    );
+    // only the direction that divides by its price fails, the other has a price product of 0
+    assert_eq!(scan.failed.len(), 2);
+    for failed in &scan.failed {
+        assert_eq!(failed.token, pool.token0);
+        assert_eq!(failed.pools[1], uninitialized.metadata.address);
+        assert!(matches!(
+            failed.error,
+            QuoteError::Swap(UniswapV3MathError::DenominatorIsZero)
+        ));
+    }
+}
@@ -0,0 +1,163 @@
+use alloy_primitives::{Address, Bytes, I256, U256};
+
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::ledger::LedgerError;
+use crate::libraries::error::UniswapV3MathError;
+use crate::pool_data::{SwapParams, SwapResult};
+
+#[derive(Error, Debug)]
+pub enum CallbackError {
+    #[error("the pool was not paid the input amount of the swap")]
+    InsufficientInputAmount,
+    #[error("transfer failed: {0}")]
+    Ledger(#[from] LedgerError),
+    #[error("swap failed: {0}")]
+    Swap(#[from] UniswapV3MathError),
+}
+
+/// Token balances the pool pays from and is paid into.
+pub trait Balances {
+    fn balance_of(&self, token: Address, holder: Address) -> U256;
+
+    /// Moves `amount` of `token` from `from` to `to`, failing if `from` doesn't hold enough.
+    fn transfer(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), LedgerError>;
+}
+
+/// Passes transfers through to `balances` and records them, so they can be reverted when the
+/// swap or flash they settle fails.
+pub(crate) struct Journal<'a> {
+    balances: &'a mut dyn Balances,
+    transfers: Vec<(Address, Address, Address, U256)>,
+}
+
+impl<'a> Journal<'a> {
+    pub(crate) fn new(balances: &'a mut dyn Balances) -> Self {
+        Self {
+            balances,
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Undoes the recorded transfers, latest first.
+    pub(crate) fn revert(self) {
+        for (token, from, to, amount) in self.transfers.into_iter().rev() {
+            self.balances
+                .transfer(token, to, from, amount)
+                .expect("the recipient of a transfer holds the amount until it is reverted");
+        }
+    }
+}
+
+impl Balances for Journal<'_> {
+    fn balance_of(&self, token: Address, holder: Address) -> U256 {
+        self.balances.balance_of(token, holder)
+    }
+
+    fn transfer(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), LedgerError> {
+        self.balances.transfer(token, from, to, amount)?;
+        self.transfers.push((token, from, to, amount));
+        Ok(())
+    }
+}
+
+/// Counterpart of `IUniswapV3SwapCallback`, implemented by whoever initiates a swap.
+pub trait SwapCallback {
+    /// Called once the output tokens have been sent to the recipient. `pool` must be paid the
+    /// positive one of the two deltas, in token0 for `amount0_delta` and in token1 for
+    /// `amount1_delta`. `data` is passed through from `SwapParams`.
+    fn uniswap_v3_swap_callback(
+        &mut self,
+        balances: &mut dyn Balances,
+        pool: Address,
+        amount0_delta: I256,
+        amount1_delta: I256,
+        data: &Bytes,
+    ) -> Result<(), CallbackError>;
+}
+
+impl<F> SwapCallback for F
+where
+    F: FnMut(&mut dyn Balances, Address, I256, I256, &Bytes) -> Result<(), CallbackError>,
+{
+    fn uniswap_v3_swap_callback(
+        &mut self,
+        balances: &mut dyn Balances,
+        pool: Address,
+        amount0_delta: I256,
+        amount1_delta: I256,
+        data: &Bytes,
+    ) -> Result<(), CallbackError> {
+        self(balances, pool, amount0_delta, amount1_delta, data)
+    }
+}
+
+impl UniswapV3Pool {
+    /// Same as `swap`, additionally settling the swap in `balances` the way the contract does:
+    /// the output is transferred from the pool to `params.recipient`, then `callback` is
+    /// invoked and the pool's balance of the input token must have grown by the input amount.
+    /// The pool holds its tokens at `metadata.address`.
+    ///
+    /// The callback can't observe the pool, so the state transition is only committed once the
+    /// pool has been paid. On error the transfers made so far are reverted, leaving both the
+    /// pool and `balances` unchanged.
+    pub fn swap_with_callback(
+        &mut self,
+        params: SwapParams,
+        balances: &mut dyn Balances,
+        callback: &mut dyn SwapCallback,
+    ) -> Result<SwapResult, CallbackError> {
+        let result = self.compute_swap(&params, false)?;
+        let pool = self.metadata.address;
+
+        let (token_in, amount_in, token_out, amount_out) = if params.zero_for_one {
+            (self.token0, result.amount0, self.token1, result.amount1)
+        } else {
+            (self.token1, result.amount1, self.token0, result.amount0)
+        };
+
+        let mut journal = Journal::new(balances);
+        let mut settle = |balances: &mut dyn Balances| {
+            // do the transfers and collect payment
+            if amount_out < I256::ZERO {
+                balances.transfer(token_out, pool, params.recipient, (-amount_out).into_raw())?;
+            }
+
+            let balance_before = balances.balance_of(token_in, pool);
+            callback.uniswap_v3_swap_callback(
+                balances,
+                pool,
+                result.amount0,
+                result.amount1,
+                &params.data,
+            )?;
+            let paid = balance_before
+                .checked_add(amount_in.into_raw())
+                .is_some_and(|required| required <= balances.balance_of(token_in, pool));
+            if !paid {
+                return Err(CallbackError::InsufficientInputAmount);
+            }
+            Ok(())
+        };
+        if let Err(err) = settle(&mut journal) {
+            journal.revert();
+            return Err(err);
+        }
+
+        self.apply(&result);
+        Ok(result)
+    }
+}
@@ -0,0 +1,145 @@
+use super::*;
+use crate::callback::{Balances, CallbackError, SwapCallback};
+use crate::ledger::{Ledger, LedgerError};
+use crate::test_utils::{SNAPSHOT, address, i256};
+use alloy_primitives::Bytes;
+
+/// Pays the positive delta from `payer`, optionally short by one wei.
+struct Payer {
+    payer: Address,
+    token0: Address,
+    token1: Address,
+    short: bool,
+    calls: Vec<(I256, I256, Bytes)>,
+}
+
+impl SwapCallback for Payer {
+    fn uniswap_v3_swap_callback(
+        &mut self,
+        balances: &mut dyn Balances,
+        pool: Address,
+        amount0_delta: I256,
+        amount1_delta: I256,
+        data: &Bytes,
+    ) -> Result<(), CallbackError> {
+        self.calls
+            .push((amount0_delta, amount1_delta, data.clone()));
+        let (token, amount) = if amount0_delta > I256::ZERO {
+            (self.token0, amount0_delta)
+        } else {
+            (self.token1, amount1_delta)
+        };
+        let amount = amount.into_raw() - U256::from(self.short as u8);
+        Ok(balances.transfer(token, self.payer, pool, amount)?)
+    }
+}
+
+fn setup() -> (UniswapV3Pool, Ledger, Payer) {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let payer = address("0x13f4ea83d0bd40e75c8222255bc855a974568dd4");
+    let mut balances = Ledger::new();
+    for token in [pool.token0, pool.token1] {
+        balances.set_balance(token, pool.metadata.address, U256::from(10u128.pow(24)));
+        balances.set_balance(token, payer, U256::from(10u128.pow(24)));
+    }
+    let callback = Payer {
+        payer,
+        token0: pool.token0,
+        token1: pool.token1,
+        short: false,
+        calls: Vec::new(),
+    };
+    (pool, balances, callback)
+}
+
+#[test]
+fn test_swap_with_callback() {
+    let (mut pool, mut balances, mut callback) = setup();
+    let recipient = address("0x0000000000000000000000000000000000000001");
+    let pool_address = pool.metadata.address;
+    let mut params = SwapParams::exact_input(recipient, false, i256("1000000000000000000"));
+    params.data = Bytes::from_static(b"hop");
+
+    let expected = pool.quote(&params).unwrap();
+    let result = pool
+        .swap_with_callback(params, &mut balances, &mut callback)
+        .unwrap();
+    assert_eq!(result.amount0, expected.amount0);
+    assert_eq!(result.amount1, expected.amount1);
+    assert_eq!(pool.slot0.sqrt_price_x96, expected.sqrt_price_x96);
+
+    assert_eq!(
+        callback.calls,
+        vec![(result.amount0, result.amount1, Bytes::from_static(b"hop"))]
+    );
+    assert_eq!(
+        balances.balance_of(pool.token0, recipient),
+        (-result.amount0).into_raw()
+    );
+    assert_eq!(
+        balances.balance_of(pool.token0, pool_address),
+        U256::from(10u128.pow(24)) - (-result.amount0).into_raw()
+    );
+    assert_eq!(
+        balances.balance_of(pool.token1, pool_address),
+        U256::from(10u128.pow(24)) + result.amount1.into_raw()
+    );
+}
+
+#[test]
+fn test_swap_with_callback_closure() {
+    let (mut pool, mut balances, callback) = setup();
+    let payer = callback.payer;
+    let token0 = pool.token0;
+    let params = SwapParams::exact_output(payer, true, i256("1000000"));
+
+    let result = pool
+        .swap_with_callback(
+            params,
+            &mut balances,
+            &mut |balances: &mut dyn Balances,
+                  pool: Address,
+                  amount0_delta: I256,
+                  _: I256,
+                  _: &Bytes| {
+                Ok(balances.transfer(token0, payer, pool, amount0_delta.into_raw())?)
+            },
+        )
+        .unwrap();
+    assert_eq!(result.amount1, i256("-1000000"));
+}
+
+#[test]
+fn test_swap_with_callback_insufficient_input() {
+    let (mut pool, mut balances, mut callback) = setup();
+    callback.short = true;
+    let slot0 = pool.slot0.clone();
+    let balances_before = balances.clone();
+    let params = SwapParams::exact_input(callback.payer, true, i256("1000000000000000000"));
+
+    let result = pool.swap_with_callback(params, &mut balances, &mut callback);
+    assert!(matches!(
+        result,
+        Err(CallbackError::InsufficientInputAmount)
+    ));
+    assert_eq!(pool.slot0.sqrt_price_x96, slot0.sqrt_price_x96);
+    assert_eq!(pool.slot0.tick, slot0.tick);
+    // the output sent to the recipient is taken back
+    assert_eq!(balances, balances_before);
+}
+
+#[test]
+fn test_swap_with_callback_insufficient_reserves() {
+    let (mut pool, mut balances, mut callback) = setup();
+    let pool_address = pool.metadata.address;
+    balances.set_balance(pool.token0, pool_address, U256::from(1));
+    let params = SwapParams::exact_input(callback.payer, false, i256("1000000000000000000"));
+
+    let result = pool.swap_with_callback(params, &mut balances, &mut callback);
+    assert!(matches!(
+        result,
+        Err(CallbackError::Ledger(LedgerError::InsufficientBalance { token, holder }))
+            if token == pool.token0 && holder == pool_address
+    ));
+    assert!(callback.calls.is_empty());
+}
@@ -0,0 +1,236 @@
+use alloy_primitives::{I256, U256};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::full_math::mul_div;
+use crate::libraries::sqrt_price_math::Q96;
+use crate::libraries::tick_math;
+use crate::snapshot::decimal;
+
+const BPS: u64 = 10_000;
+
+/// Price moves in basis points that depth profiles are usually computed for.
+pub const DEPTH_LEVELS_BPS: [u16; 6] = [10, 50, 100, 200, 500, 1000];
+
+/// The swap that moves the price of token0 by `move_bps`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DepthLevel {
+    /// Negative when the price falls, i.e. for token0 sold into the pool.
+    pub move_bps: i32,
+    pub zero_for_one: bool,
+    #[serde(serialize_with = "decimal::serialize")]
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    /// Input to swap to reach the price, including `fee_amount`.
+    #[serde(serialize_with = "decimal::serialize")]
+    pub amount_in: U256,
+    #[serde(serialize_with = "decimal::serialize")]
+    pub amount_out: U256,
+    #[serde(serialize_with = "decimal::serialize")]
+    pub fee_amount: U256,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DepthProfile {
+    #[serde(serialize_with = "decimal::serialize")]
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    /// Ordered by `move_bps`, from the largest fall to the largest rise.
+    pub levels: Vec<DepthLevel>,
+}
+
+/// One sampled swap of a price impact curve.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ImpactPoint {
+    #[serde(serialize_with = "decimal::serialize")]
+    pub amount_in: U256,
+    #[serde(serialize_with = "decimal::serialize")]
+    pub amount_out: U256,
+    #[serde(serialize_with = "decimal::serialize")]
+    pub fee_amount: U256,
+    /// Token1 per token0 paid or received on average, as a Q96.
+    #[serde(serialize_with = "decimal::serialize")]
+    pub effective_price_x96: U256,
+    /// How much worse the effective price is than the mid price, fee included.
+    pub slippage_bps: f64,
+    /// Price after the swap.
+    #[serde(serialize_with = "decimal::serialize")]
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ImpactCurve {
+    pub zero_for_one: bool,
+    /// Token1 per token0 at the current price, as a Q96.
+    #[serde(serialize_with = "decimal::serialize")]
+    pub mid_price_x96: U256,
+    pub points: Vec<ImpactPoint>,
+}
+
+impl UniswapV3Pool {
+    /// Computes the input needed to move the price of token0 up and down by each of
+    /// `moves_bps`, e.g. `DEPTH_LEVELS_BPS`. Amounts are cumulative from the current price and
+    /// exact, see `amount_to_reach_sqrt_price`. Levels past the lowest or highest price of the
+    /// pool, such as a fall of 10000 bps or more, stop at that price.
+    pub fn depth_profile(&self, moves_bps: &[u16]) -> Result<DepthProfile, UniswapV3MathError> {
+        let mut moves: Vec<i32> = moves_bps
+            .iter()
+            .flat_map(|&bps| [-i32::from(bps), i32::from(bps)])
+            .collect();
+        moves.sort_unstable();
+        moves.dedup();
+
+        let mut levels = Vec::with_capacity(moves.len());
+        for move_bps in moves {
+            // the square root of the price ratio scales the sqrt price
+            let ratio = U256::from((BPS as i64 + move_bps as i64).max(0));
+            let sqrt_ratio_x96 = ((ratio << 192usize) / U256::from(BPS)).root(2);
+            // moves beyond the prices the pool can reach stop at its limits
+            let target = mul_div(self.slot0.sqrt_price_x96, sqrt_ratio_x96, Q96)?.clamp(
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                tick_math::MAX_SQRT_RATIO - U256::from(1),
+            );
+            let amounts = self.amount_to_reach_sqrt_price(target)?;
+            levels.push(DepthLevel {
+                move_bps,
+                zero_for_one: amounts.zero_for_one,
+                sqrt_price_x96: amounts.sqrt_price_x96,
+                tick: amounts.tick,
+                amount_in: amounts.amount_in,
+                amount_out: amounts.amount_out,
+                fee_amount: amounts.fee_amount,
+            });
+        }
+
+        Ok(DepthProfile {
+            sqrt_price_x96: self.slot0.sqrt_price_x96,
+            tick: self.slot0.tick,
+            levels,
+        })
+    }
+
+    /// Simulates a swap of each of `amounts` from the current state. The amounts are specified
+    /// as in `SwapParams::amount_specified`, positive for exact input and negative for exact
+    /// output. Amounts too small to swap anything are left out.
+    pub fn price_impact_curve(
+        &self,
+        zero_for_one: bool,
+        amounts: &[I256],
+    ) -> Result<ImpactCurve, UniswapV3MathError> {
+        let sqrt_price_x96 = self.slot0.sqrt_price_x96;
+        let mid_price_x96 = mul_div(sqrt_price_x96, sqrt_price_x96, Q96)?;
+
+        let mut points = Vec::with_capacity(amounts.len());
+        for &amount in amounts {
+            if amount == I256::ZERO {
+                continue;
+            }
+            let result = if amount > I256::ZERO {
+                self.quote_exact_input(zero_for_one, amount)?
+            } else {
+                self.quote_exact_output(zero_for_one, -amount)?
+            };
+            let (amount_in, amount_out) = if zero_for_one {
+                (result.amount0.into_raw(), (-result.amount1).into_raw())
+            } else {
+                (result.amount1.into_raw(), (-result.amount0).into_raw())
+            };
+            if amount_in == U256::ZERO || amount_out == U256::ZERO {
+                continue;
+            }
+
+            let effective_price_x96 = if zero_for_one {
+                mul_div(amount_out, Q96, amount_in)?
+            } else {
+                mul_div(amount_in, Q96, amount_out)?
+            };
+            // selling token0 gets a lower price than the mid and buying it a higher one
+            let worse = if zero_for_one {
+                I256::from_raw(mid_price_x96) - I256::from_raw(effective_price_x96)
+            } else {
+                I256::from_raw(effective_price_x96) - I256::from_raw(mid_price_x96)
+            };
+            let slippage_bps_x100 =
+                mul_div(worse.unsigned_abs(), U256::from(BPS * 100), mid_price_x96)?;
+            let slippage_bps = slippage_bps_x100.saturating_to::<u128>() as f64 / 100.0;
+            points.push(ImpactPoint {
+                amount_in,
+                amount_out,
+                fee_amount: result.fee_amount,
+                effective_price_x96,
+                slippage_bps: if worse.is_negative() {
+                    -slippage_bps
+                } else {
+                    slippage_bps
+                },
+                sqrt_price_x96: result.sqrt_price_x96,
+                tick: result.tick,
+            });
+        }
+
+        Ok(ImpactCurve {
+            zero_for_one,
+            mid_price_x96,
+            points,
+        })
+    }
+}
+
+impl DepthProfile {
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Writes one row per level, with amounts in the smallest unit of their token.
+    pub fn write_csv(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "move_bps,zero_for_one,sqrt_price_x96,tick,amount_in,amount_out,fee_amount"
+        )?;
+        for level in &self.levels {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                level.move_bps,
+                level.zero_for_one,
+                level.sqrt_price_x96,
+                level.tick,
+                level.amount_in,
+                level.amount_out,
+                level.fee_amount
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ImpactCurve {
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Writes one row per point, with amounts in the smallest unit of their token.
+    pub fn write_csv(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "amount_in,amount_out,fee_amount,effective_price_x96,slippage_bps,sqrt_price_x96,tick"
+        )?;
+        for point in &self.points {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                point.amount_in,
+                point.amount_out,
+                point.fee_amount,
+                point.effective_price_x96,
+                point.slippage_bps,
+                point.sqrt_price_x96,
+                point.tick
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+use crate::depth::DEPTH_LEVELS_BPS;
+use crate::test_utils::{SNAPSHOT, i256};
+
+#[test]
+fn test_depth_profile() {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let profile = pool.depth_profile(&DEPTH_LEVELS_BPS).unwrap();
+    assert_eq!(profile.levels.len(), 12);
+    assert_eq!(profile.levels[0].move_bps, -1000);
+    assert_eq!(profile.levels[11].move_bps, 1000);
+
+    for level in &profile.levels {
+        assert_eq!(level.zero_for_one, level.move_bps < 0);
+        // a swap limited at the level's price takes exactly the level's amounts to get there
+        let mut params = SwapParams::exact_input(
+            Address::ZERO,
+            level.zero_for_one,
+            i256("1000000000000000000000000000000"),
+        );
+        params.sqrt_price_limit_x96 = level.sqrt_price_x96;
+        let result = pool.clone().swap(params).unwrap();
+        let (amount_in, amount_out) = if level.zero_for_one {
+            (result.amount0, -result.amount1)
+        } else {
+            (result.amount1, -result.amount0)
+        };
+        assert_eq!(result.sqrt_price_x96, level.sqrt_price_x96);
+        assert_eq!(result.tick, level.tick);
+        assert_eq!(level.amount_in, amount_in.into_raw());
+        assert_eq!(level.amount_out, amount_out.into_raw());
+        assert_eq!(level.fee_amount, result.fee_amount);
+
+        // the sqrt price moves by the square root of the price move
+        let price = level.sqrt_price_x96.pow(U256::from(2));
+        let expected = pool.slot0.sqrt_price_x96.pow(U256::from(2))
+            * U256::from(10_000 + level.move_bps as i64)
+            / U256::from(10_000);
+        assert!(price.abs_diff(expected) < expected >> 90);
+    }
+
+    // larger moves take more input on both sides
+    for side in [&profile.levels[..6], &profile.levels[6..]] {
+        let mut amounts: Vec<U256> = side.iter().map(|level| level.amount_in).collect();
+        if side[0].move_bps < 0 {
+            amounts.reverse();
+        }
+        assert!(amounts.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
+
+#[test]
+fn test_depth_profile_beyond_price_limits() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let profile = pool.depth_profile(&[10_000, 20_000]).unwrap();
+    let lowest = tick_math::MIN_SQRT_RATIO + U256::from(1);
+    assert_eq!(profile.levels[0].sqrt_price_x96, lowest);
+    assert_eq!(profile.levels[1].sqrt_price_x96, lowest);
+    assert_eq!(profile.levels[1].amount_in, profile.levels[0].amount_in);
+    assert!(profile.levels[3].sqrt_price_x96 < tick_math::MAX_SQRT_RATIO);
+
+    // the largest move there is doesn't wrap around
+    let profile = pool.depth_profile(&[u16::MAX]).unwrap();
+    assert_eq!(profile.levels[0].move_bps, -65535);
+    assert_eq!(profile.levels[1].move_bps, 65535);
+    assert!(profile.levels[1].sqrt_price_x96 > pool.slot0.sqrt_price_x96);
+
+    // doubling the price from near the top of the range goes past the highest price
+    pool.slot0.tick = tick_math::MAX_TICK - 1000;
+    pool.slot0.sqrt_price_x96 = tick_math::get_sqrt_ratio_at_tick(pool.slot0.tick).unwrap();
+    let profile = pool.depth_profile(&[10_000]).unwrap();
+    assert_eq!(
+        profile.levels[1].sqrt_price_x96,
+        tick_math::MAX_SQRT_RATIO - U256::from(1)
+    );
+    assert!(profile.levels[1].amount_in > U256::ZERO);
+}
+
+#[test]
+fn test_price_impact_curve() {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let amounts: Vec<I256> = (18..26)
+        .map(|exponent| I256::try_from(10u128.pow(exponent)).unwrap())
+        .collect();
+
+    for zero_for_one in [true, false] {
+        let curve = pool.price_impact_curve(zero_for_one, &amounts).unwrap();
+        assert_eq!(curve.points.len(), amounts.len());
+        for (point, amount) in curve.points.iter().zip(&amounts) {
+            assert_eq!(point.amount_in, amount.into_raw());
+            let result = pool.quote_exact_input(zero_for_one, *amount).unwrap();
+            assert_eq!(point.sqrt_price_x96, result.sqrt_price_x96);
+        }
+        // slippage starts at the fee and grows with the size of the swap
+        assert!((curve.points[0].slippage_bps - 1.0).abs() < 0.1);
+        assert!(
+            curve
+                .points
+                .windows(2)
+                .all(|pair| pair[0].slippage_bps <= pair[1].slippage_bps)
+        );
+    }
+
+    // exact output amounts are negative, and zero amounts are skipped
+    let curve = pool
+        .price_impact_curve(true, &[I256::ZERO, -i256("1000000000000000000000")])
+        .unwrap();
+    assert_eq!(curve.points.len(), 1);
+    assert_eq!(curve.points[0].amount_out, U256::from(10u128.pow(21)));
+}
+
+#[test]
+fn test_export() {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let profile = pool.depth_profile(&[100]).unwrap();
+
+    let mut csv = Vec::new();
+    profile.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0],
+        "move_bps,zero_for_one,sqrt_price_x96,tick,amount_in,amount_out,fee_amount"
+    );
+    assert!(lines[1].starts_with("-100,true,"));
+    assert!(lines[2].ends_with(&format!(",{}", profile.levels[1].fee_amount)));
+
+    let json: serde_json::Value = serde_json::from_str(&profile.to_json_string().unwrap()).unwrap();
+    assert_eq!(json["tick"], 20603);
+    assert_eq!(json["levels"][1]["move_bps"], 100);
+    assert_eq!(
+        json["levels"][1]["amount_in"],
+        profile.levels[1].amount_in.to_string()
+    );
+
+    let curve = pool
+        .price_impact_curve(false, &[i256("1000000000000000000000")])
+        .unwrap();
+    let mut csv = Vec::new();
+    curve.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv.starts_with("amount_in,amount_out,fee_amount,effective_price_x96,slippage_bps,"));
+    let json: serde_json::Value = serde_json::from_str(&curve.to_json_string().unwrap()).unwrap();
+    assert_eq!(json["zero_for_one"], false);
+    assert_eq!(json["points"][0]["amount_in"], "1000000000000000000000");
+    assert_eq!(
+        json["points"][0]["slippage_bps"],
+        curve.points[0].slippage_bps
+    );
+}
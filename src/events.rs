@@ -0,0 +1,295 @@
+use alloy_primitives::{Address, I256, U256};
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::flash::FlashError;
+use crate::libraries::error::UniswapV3MathError;
+use crate::pool_data::{PositionInfo, SwapParams};
+
+#[derive(Error, Debug)]
+pub enum EventError {
+    #[error("{0}")]
+    SwapReplayMismatch(Box<SwapReplayMismatch>),
+    #[error("fee protocols {fee_protocol0} and {fee_protocol1} are rejected by the pool")]
+    InvalidFeeProtocol {
+        fee_protocol0: u32,
+        fee_protocol1: u32,
+    },
+    #[error("flash failed: {0}")]
+    Flash(#[from] FlashError),
+    #[error("pool error: {0}")]
+    Pool(#[from] UniswapV3MathError),
+}
+
+/// Amounts of a swap event that no simulation reproduced, next to those of the simulated swap
+/// that was applied instead.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "swap event amounts {amount0}, {amount1} were simulated as {simulated_amount0}, \
+     {simulated_amount1}"
+)]
+pub struct SwapReplayMismatch {
+    pub amount0: I256,
+    pub amount1: I256,
+    pub simulated_amount0: I256,
+    pub simulated_amount1: I256,
+}
+
+/// A decoded event emitted by the pool, as consumed by `UniswapV3Pool::apply_log`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PoolEvent {
+    Swap {
+        sender: Address,
+        recipient: Address,
+        amount0: I256,
+        amount1: I256,
+        sqrt_price_x96: U256,
+        liquidity: u128,
+        tick: i32,
+        /// Protocol fees of the swap in token0 and token1, only emitted by PancakeSwap V3 pools.
+        protocol_fees: Option<(u128, u128)>,
+    },
+    Mint {
+        sender: Address,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+        amount0: U256,
+        amount1: U256,
+    },
+    Burn {
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+        amount0: U256,
+        amount1: U256,
+    },
+    Collect {
+        owner: Address,
+        recipient: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0: u128,
+        amount1: u128,
+    },
+    Flash {
+        sender: Address,
+        recipient: Address,
+        amount0: U256,
+        amount1: U256,
+        paid0: U256,
+        paid1: U256,
+    },
+    SetFeeProtocol {
+        /// uint8s on Uniswap and uint32s on PancakeSwap, see `FeeProtocolLayout`.
+        fee_protocol0_old: u32,
+        fee_protocol1_old: u32,
+        fee_protocol0_new: u32,
+        fee_protocol1_new: u32,
+    },
+    CollectProtocol {
+        sender: Address,
+        recipient: Address,
+        amount0: u128,
+        amount1: u128,
+    },
+    IncreaseObservationCardinalityNext {
+        observation_cardinality_next_old: u16,
+        observation_cardinality_next_new: u16,
+    },
+}
+
+impl UniswapV3Pool {
+    /// Updates the pool with an event it emitted, in the order the events were emitted.
+    ///
+    /// Swaps are re-simulated from the amounts of the event to account for fees and crossed
+    /// ticks, after which the price, tick and liquidity are taken from the event, as are the
+    /// protocol fees if the event carries them. If no simulation reproduces the amounts of the
+    /// event, the pool is still moved to the state of the event and `SwapReplayMismatch` is
+    /// returned, as the fees it accrued may be off. Positions opened before the pool was loaded
+    /// are picked up when they are burned, without any fees owed. Events are applied at
+    /// `block_timestamp`, which has to be kept up to date for the oracle.
+    pub fn apply_log(&mut self, event: &PoolEvent) -> Result<(), EventError> {
+        match *event {
+            PoolEvent::Swap {
+                amount0,
+                amount1,
+                sqrt_price_x96,
+                liquidity,
+                tick,
+                protocol_fees,
+                ..
+            } => {
+                let protocol_fees_before = self.protocol_fees.clone();
+                let replayed = if amount0 != I256::ZERO || amount1 != I256::ZERO {
+                    self.replay_swap(amount0, amount1, sqrt_price_x96)
+                } else {
+                    Ok(())
+                };
+                if let Some((token0, token1)) = protocol_fees {
+                    self.protocol_fees.token0 = protocol_fees_before.token0.wrapping_add(token0);
+                    self.protocol_fees.token1 = protocol_fees_before.token1.wrapping_add(token1);
+                }
+                self.slot0.sqrt_price_x96 = sqrt_price_x96;
+                self.slot0.tick = tick;
+                self.liquidity = liquidity;
+                replayed?;
+            }
+            PoolEvent::Mint {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                ..
+            } => {
+                self.mint(owner, tick_lower, tick_upper, amount)?;
+            }
+            PoolEvent::Burn {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                ..
+            } => {
+                // a zero amount burn only pokes the fees owed, which aren't known for positions
+                // opened before the pool was loaded
+                let key = (owner, tick_lower, tick_upper);
+                if amount > 0 || self.positions.contains_key(&key) {
+                    self.adopt_position(owner, tick_lower, tick_upper, amount);
+                    self.burn(owner, tick_lower, tick_upper, amount)?;
+                }
+            }
+            PoolEvent::Collect {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount0,
+                amount1,
+                ..
+            } => {
+                self.collect(owner, tick_lower, tick_upper, amount0, amount1);
+            }
+            PoolEvent::Flash {
+                amount0,
+                amount1,
+                paid0,
+                paid1,
+                ..
+            } => {
+                self.flash(amount0, amount1, |_, _| (amount0 + paid0, amount1 + paid1))?;
+            }
+            PoolEvent::SetFeeProtocol {
+                fee_protocol0_new,
+                fee_protocol1_new,
+                ..
+            } => {
+                self.slot0.fee_protocol = self
+                    .metadata
+                    .fee_protocol_layout()
+                    .pack(fee_protocol0_new, fee_protocol1_new)
+                    .ok_or(EventError::InvalidFeeProtocol {
+                        fee_protocol0: fee_protocol0_new,
+                        fee_protocol1: fee_protocol1_new,
+                    })?;
+            }
+            PoolEvent::CollectProtocol {
+                amount0, amount1, ..
+            } => {
+                self.collect_protocol(amount0, amount1);
+            }
+            PoolEvent::IncreaseObservationCardinalityNext {
+                observation_cardinality_next_new,
+                ..
+            } => {
+                // pools loaded without observations don't track the oracle
+                if self.slot0.observation_cardinality > 0 {
+                    self.increase_observation_cardinality_next(observation_cardinality_next_new)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the swap that moved the price to `sqrt_price_x96` with the given amounts. The
+    /// swap may have specified either its input or its output, with or without a price limit,
+    /// so each is tried until one reproduces the event. If none does, the last one that could be
+    /// simulated is applied and `SwapReplayMismatch` returned.
+    fn replay_swap(
+        &mut self,
+        amount0: I256,
+        amount1: I256,
+        sqrt_price_x96: U256,
+    ) -> Result<(), EventError> {
+        let zero_for_one = amount0 > I256::ZERO || amount1 < I256::ZERO;
+        let (amount_in, amount_out) = if zero_for_one {
+            (amount0, -amount1)
+        } else {
+            (amount1, -amount0)
+        };
+
+        let mut limited = SwapParams::exact_input(Address::ZERO, zero_for_one, amount_in);
+        limited.sqrt_price_limit_x96 = sqrt_price_x96;
+        let candidates = [
+            SwapParams::exact_input(Address::ZERO, zero_for_one, amount_in),
+            SwapParams::exact_output(Address::ZERO, zero_for_one, amount_out),
+            limited,
+        ];
+
+        let mut last = None;
+        for params in candidates {
+            let result = match self.quote(&params) {
+                Ok(result) => result,
+                Err(error) => {
+                    last = last.or(Some(Err(error)));
+                    continue;
+                }
+            };
+            if result.sqrt_price_x96 == sqrt_price_x96
+                && result.amount0 == amount0
+                && result.amount1 == amount1
+            {
+                self.apply(&result);
+                return Ok(());
+            }
+            last = Some(Ok(result));
+        }
+
+        // with no candidate simulated, the error of the first one is the most telling
+        let result = last.expect("at least one candidate")?;
+        self.apply(&result);
+        Err(EventError::SwapReplayMismatch(Box::new(
+            SwapReplayMismatch {
+                amount0,
+                amount1,
+                simulated_amount0: result.amount0,
+                simulated_amount1: result.amount1,
+            },
+        )))
+    }
+
+    /// Makes sure the position holds at least `liquidity`, for positions opened before the pool
+    /// was loaded. Their liquidity is already part of the ticks, so only the position is created,
+    /// with no fees owed.
+    fn adopt_position(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) {
+        let (fee_growth_inside0_x128, fee_growth_inside1_x128) =
+            self.get_fee_growth_inside(tick_lower, tick_upper);
+        let position = self
+            .positions
+            .entry((owner, tick_lower, tick_upper))
+            .or_insert_with(|| PositionInfo {
+                fee_growth_inside0_last_x128: fee_growth_inside0_x128,
+                fee_growth_inside1_last_x128: fee_growth_inside1_x128,
+                ..Default::default()
+            });
+        position.liquidity = position.liquidity.max(liquidity);
+    }
+}
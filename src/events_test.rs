@@ -0,0 +1,335 @@
+use super::*;
+use crate::events::{EventError, PoolEvent, SwapReplayMismatch};
+use crate::test_utils::{SNAPSHOT, SNAPSHOT_2, i256, owner};
+
+fn swap_event(result: &SwapResult) -> PoolEvent {
+    PoolEvent::Swap {
+        sender: owner(),
+        recipient: owner(),
+        amount0: result.amount0,
+        amount1: result.amount1,
+        sqrt_price_x96: result.sqrt_price_x96,
+        liquidity: result.liquidity,
+        tick: result.tick,
+        protocol_fees: None,
+    }
+}
+
+fn mint_event(
+    pool: &mut UniswapV3Pool,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount: u128,
+) -> PoolEvent {
+    let (amount0, amount1) = pool.mint(owner(), tick_lower, tick_upper, amount).unwrap();
+    PoolEvent::Mint {
+        sender: owner(),
+        owner: owner(),
+        tick_lower,
+        tick_upper,
+        amount,
+        amount0,
+        amount1,
+    }
+}
+
+fn burn_event(
+    pool: &mut UniswapV3Pool,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount: u128,
+) -> PoolEvent {
+    let (amount0, amount1) = pool.burn(owner(), tick_lower, tick_upper, amount).unwrap();
+    PoolEvent::Burn {
+        owner: owner(),
+        tick_lower,
+        tick_upper,
+        amount,
+        amount0,
+        amount1,
+    }
+}
+
+fn assert_same_state(replayed: &UniswapV3Pool, expected: &UniswapV3Pool) {
+    assert_eq!(
+        replayed.to_snapshot().to_json_string().unwrap(),
+        expected.to_snapshot().to_json_string().unwrap()
+    );
+    assert_eq!(replayed.positions, expected.positions);
+}
+
+#[test]
+fn test_replay_simulated_logs() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let mut replayed = pool.clone();
+    let mut events = Vec::new();
+
+    // exact input, crossing into the minted range on the way back down
+    let result = pool
+        .swap(SwapParams::exact_input(
+            owner(),
+            false,
+            i256("1000000000000000000000"),
+        ))
+        .unwrap();
+    events.push(swap_event(&result));
+    events.push(mint_event(&mut pool, 20000, 21000, 10u128.pow(21)));
+    let result = pool
+        .swap(SwapParams::exact_output(
+            owner(),
+            true,
+            i256("100000000000000000000000"),
+        ))
+        .unwrap();
+    assert!(!result.ticks_crossed.is_empty());
+    events.push(swap_event(&result));
+
+    let (paid0, paid1) = pool
+        .flash(U256::from(10u128.pow(21)), U256::ZERO, |fee0, _| {
+            (
+                U256::from(10u128.pow(21)) + fee0 + U256::from(7),
+                U256::ZERO,
+            )
+        })
+        .unwrap();
+    events.push(PoolEvent::Flash {
+        sender: owner(),
+        recipient: owner(),
+        amount0: U256::from(10u128.pow(21)),
+        amount1: U256::ZERO,
+        paid0,
+        paid1,
+    });
+
+    pool.slot0.fee_protocol = 3300 | (2500 << 16);
+    events.push(PoolEvent::SetFeeProtocol {
+        fee_protocol0_old: 0,
+        fee_protocol1_old: 0,
+        fee_protocol0_new: 3300,
+        fee_protocol1_new: 2500,
+    });
+
+    // stopped by the price limit before the input is used up
+    let mut params = SwapParams::exact_input(owner(), false, i256("100000000000000000000000000"));
+    params.sqrt_price_limit_x96 = tick_math::get_sqrt_ratio_at_tick(21500).unwrap();
+    let result = pool.swap(params).unwrap();
+    assert_eq!(
+        result.sqrt_price_x96,
+        tick_math::get_sqrt_ratio_at_tick(21500).unwrap()
+    );
+    events.push(swap_event(&result));
+
+    events.push(burn_event(&mut pool, 20000, 21000, 10u128.pow(21)));
+    let (amount0, amount1) = pool.collect(owner(), 20000, 21000, u128::MAX, u128::MAX);
+    events.push(PoolEvent::Collect {
+        owner: owner(),
+        recipient: owner(),
+        tick_lower: 20000,
+        tick_upper: 21000,
+        amount0,
+        amount1,
+    });
+    let (amount0, amount1) = pool.collect_protocol(u128::MAX, u128::MAX);
+    events.push(PoolEvent::CollectProtocol {
+        sender: owner(),
+        recipient: owner(),
+        amount0,
+        amount1,
+    });
+
+    for event in &events {
+        replayed.apply_log(event).unwrap();
+    }
+    assert_same_state(&replayed, &pool);
+}
+
+#[test]
+fn test_replay_burn_of_unknown_position() {
+    // the position is minted before the state the replay starts from is taken
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.mint(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    let mut replayed =
+        UniswapV3Pool::from_json_str(&pool.to_snapshot().to_json_string().unwrap()).unwrap();
+    assert!(replayed.positions.is_empty());
+
+    let event = burn_event(&mut pool, 20000, 21000, 4 * 10u128.pow(20));
+    replayed.apply_log(&event).unwrap();
+    let event = burn_event(&mut pool, 20000, 21000, 6 * 10u128.pow(20));
+    replayed.apply_log(&event).unwrap();
+
+    assert_eq!(
+        replayed.to_snapshot().to_json_string().unwrap(),
+        pool.to_snapshot().to_json_string().unwrap()
+    );
+    assert!(!replayed.ticks.contains_key(&20000));
+    let position = &replayed.positions[&(owner(), 20000, 21000)];
+    assert_eq!(position.liquidity, 0);
+    assert_eq!(
+        (position.tokens_owed0, position.tokens_owed1),
+        (
+            pool.positions[&(owner(), 20000, 21000)].tokens_owed0,
+            pool.positions[&(owner(), 20000, 21000)].tokens_owed1
+        )
+    );
+}
+
+#[test]
+fn test_zero_burn_of_unknown_position() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let before = pool.to_snapshot().to_json_string().unwrap();
+    let event = PoolEvent::Burn {
+        owner: owner(),
+        tick_lower: 20000,
+        tick_upper: 21000,
+        amount: 0,
+        amount0: U256::ZERO,
+        amount1: U256::ZERO,
+    };
+
+    pool.apply_log(&event).unwrap();
+    assert!(pool.positions.is_empty());
+    assert_eq!(pool.to_snapshot().to_json_string().unwrap(), before);
+}
+
+#[test]
+fn test_replay_observations() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.block_timestamp = 1000;
+    pool.initialize_oracle();
+    let mut replayed = pool.clone();
+
+    pool.increase_observation_cardinality_next(5).unwrap();
+    let event = PoolEvent::IncreaseObservationCardinalityNext {
+        observation_cardinality_next_old: 1,
+        observation_cardinality_next_new: 5,
+    };
+    replayed.apply_log(&event).unwrap();
+
+    pool.block_timestamp = 1012;
+    replayed.block_timestamp = 1012;
+    let result = pool
+        .swap(SwapParams::exact_input(
+            owner(),
+            true,
+            i256("1000000000000000000000"),
+        ))
+        .unwrap();
+    replayed.apply_log(&swap_event(&result)).unwrap();
+
+    assert_eq!(replayed.observations, pool.observations);
+    assert_eq!(replayed.slot0.observation_index, 1);
+    assert_eq!(replayed.slot0.observation_cardinality, 5);
+}
+
+#[test]
+fn test_swap_replay_mismatch() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let target = UniswapV3Pool::from_json_file(SNAPSHOT_2).unwrap();
+    let simulated = pool.quote_exact_output(false, i256("1")).unwrap();
+
+    // no swap of 1 token1 for 1 token0 moves the price there
+    let event = PoolEvent::Swap {
+        sender: owner(),
+        recipient: owner(),
+        amount0: i256("-1"),
+        amount1: i256("1"),
+        sqrt_price_x96: target.slot0.sqrt_price_x96,
+        liquidity: target.liquidity,
+        tick: target.slot0.tick,
+        protocol_fees: None,
+    };
+    match pool.apply_log(&event) {
+        Err(EventError::SwapReplayMismatch(mismatch)) => assert_eq!(
+            *mismatch,
+            SwapReplayMismatch {
+                amount0: i256("-1"),
+                amount1: i256("1"),
+                simulated_amount0: simulated.amount0,
+                simulated_amount1: simulated.amount1,
+            }
+        ),
+        other => panic!("expected a mismatch, got {other:?}"),
+    }
+
+    // the event is authoritative for the state it leaves the pool in
+    assert_eq!(pool.slot0.sqrt_price_x96, target.slot0.sqrt_price_x96);
+    assert_eq!(pool.slot0.tick, target.slot0.tick);
+    assert_eq!(pool.liquidity, target.liquidity);
+}
+
+#[test]
+fn test_pancake_swap_protocol_fees() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.slot0.fee_protocol = 3300 | (3300 << 16);
+    let mut replayed = pool.clone();
+    let fee_growth_global0_x128 = pool.fee_growth_global0_x128;
+    let liquidity = pool.liquidity;
+    let result = pool
+        .swap(SwapParams::exact_input(
+            owner(),
+            true,
+            i256("1000000000000"),
+        ))
+        .unwrap();
+    assert!(result.ticks_crossed.is_empty());
+    assert_eq!(result.fee_amount, U256::from(100000000));
+    assert_eq!(result.protocol_fee, 33000000);
+
+    let mut event = swap_event(&result);
+    if let PoolEvent::Swap { protocol_fees, .. } = &mut event {
+        *protocol_fees = Some((33000000, 0));
+    }
+    replayed.apply_log(&event).unwrap();
+    assert_eq!(replayed.protocol_fees.token0, 33000000);
+    assert_eq!(replayed.protocol_fees.token1, 0);
+    assert_eq!(replayed.slot0.sqrt_price_x96, pool.slot0.sqrt_price_x96);
+    // only the part of the fee left to the liquidity providers grows the fees per liquidity
+    let fee_growth = full_math::mul_div(
+        U256::from(100000000 - 33000000),
+        full_math::Q128,
+        U256::from(liquidity),
+    )
+    .unwrap();
+    assert_eq!(
+        replayed.fee_growth_global0_x128,
+        fee_growth_global0_x128 + fee_growth
+    );
+    assert_eq!(
+        replayed.fee_growth_global1_x128,
+        pool.fee_growth_global1_x128
+    );
+}
+
+#[test]
+fn test_set_fee_protocol() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let set_fee_protocol = |fee_protocol0_new, fee_protocol1_new| PoolEvent::SetFeeProtocol {
+        fee_protocol0_old: 0,
+        fee_protocol1_old: 0,
+        fee_protocol0_new,
+        fee_protocol1_new,
+    };
+
+    pool.apply_log(&set_fee_protocol(3300, 1000)).unwrap();
+    assert_eq!(pool.slot0.fee_protocol, 3300 | (1000 << 16));
+    let result = pool.apply_log(&set_fee_protocol(500, 0));
+    assert!(matches!(
+        result,
+        Err(EventError::InvalidFeeProtocol {
+            fee_protocol0: 500,
+            fee_protocol1: 0
+        })
+    ));
+    assert_eq!(pool.slot0.fee_protocol, 3300 | (1000 << 16));
+
+    pool.metadata.dex = "Uniswap".to_string();
+    pool.apply_log(&set_fee_protocol(4, 10)).unwrap();
+    assert_eq!(pool.slot0.fee_protocol, 0xa4);
+    pool.apply_log(&set_fee_protocol(0, 0)).unwrap();
+    assert_eq!(pool.slot0.fee_protocol, 0);
+    for (fee_protocol0, fee_protocol1) in [(3, 0), (0, 11), (255, 255)] {
+        let result = pool.apply_log(&set_fee_protocol(fee_protocol0, fee_protocol1));
+        assert!(matches!(result, Err(EventError::InvalidFeeProtocol { .. })));
+    }
+    assert_eq!(pool.slot0.fee_protocol, 0);
+}
@@ -0,0 +1,166 @@
+use alloy::eips::BlockId;
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy_primitives::aliases::I24;
+use alloy_primitives::{Address, U256};
+use indexmap::IndexMap;
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::libraries::{tick_bitmap, tick_math};
+use crate::snapshot::{
+    Snapshot, SnapshotError, SnapshotPool, SnapshotPoolStore, SnapshotProtocolFees, SnapshotSlot0,
+    SnapshotTick,
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV3Pool {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function fee() external view returns (uint24);
+        function tickSpacing() external view returns (int24);
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            // a uint8 on Uniswap, widened to a uint32 on PancakeSwap
+            uint32 feeProtocol,
+            bool unlocked
+        );
+        function feeGrowthGlobal0X128() external view returns (uint256);
+        function feeGrowthGlobal1X128() external view returns (uint256);
+        function protocolFees() external view returns (uint128 token0, uint128 token1);
+        function liquidity() external view returns (uint128);
+        function ticks(int24 tick) external view returns (
+            uint128 liquidityGross,
+            int128 liquidityNet,
+            uint256 feeGrowthOutside0X128,
+            uint256 feeGrowthOutside1X128,
+            int56 tickCumulativeOutside,
+            uint160 secondsPerLiquidityOutsideX128,
+            uint32 secondsOutside,
+            bool initialized
+        );
+        function tickBitmap(int16 wordPosition) external view returns (uint256);
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("call failed: {0}")]
+    Contract(#[from] alloy::contract::Error),
+    #[error("invalid snapshot: {0}")]
+    Snapshot(#[from] SnapshotError),
+}
+
+/// Reads the state of the pool at `address` as of `block` into the snapshot format.
+///
+/// Only the bitmap words within `word_radius` words of the current tick are read, so swaps that
+/// move the price further than that see no liquidity beyond them. The dex of the pool isn't known
+/// on chain and is taken from `dex`, e.g. `Pancake`, which decides how the fee protocol read from
+/// slot0 is unpacked. The name of the pool is left empty.
+pub async fn fetch_snapshot<P: Provider>(
+    provider: &P,
+    address: Address,
+    dex: &str,
+    block: u64,
+    word_radius: i16,
+) -> Result<Snapshot, FetchError> {
+    let pool = IUniswapV3Pool::new(address, provider);
+    let block_id = BlockId::number(block);
+
+    let token0 = pool.token0().block(block_id).call().await?;
+    let token1 = pool.token1().block(block_id).call().await?;
+    let fee = pool.fee().block(block_id).call().await?;
+    let tick_spacing = pool.tickSpacing().block(block_id).call().await?;
+    let slot0 = pool.slot0().block(block_id).call().await?;
+    let fee_growth_global0_x128 = pool.feeGrowthGlobal0X128().block(block_id).call().await?;
+    let fee_growth_global1_x128 = pool.feeGrowthGlobal1X128().block(block_id).call().await?;
+    let protocol_fees = pool.protocolFees().block(block_id).call().await?;
+    let liquidity = pool.liquidity().block(block_id).call().await?;
+
+    let spacing = tick_spacing.as_i32();
+    let (word, _) = tick_bitmap::position(slot0.tick.as_i32().div_euclid(spacing));
+    let (min_word, _) = tick_bitmap::position(tick_math::MIN_TICK / spacing);
+    let (max_word, _) = tick_bitmap::position(tick_math::MAX_TICK / spacing);
+    let word = word.as_i16();
+    let words = word.saturating_sub(word_radius).max(min_word.as_i16())
+        ..=word.saturating_add(word_radius).min(max_word.as_i16());
+
+    let mut tick_bitmap = IndexMap::new();
+    let mut ticks = IndexMap::new();
+    for word_pos in words {
+        let bitmap = pool.tickBitmap(word_pos).block(block_id).call().await?;
+        if bitmap.is_zero() {
+            continue;
+        }
+        tick_bitmap.insert(word_pos, bitmap);
+
+        for bit in (0..256).filter(|&bit| bitmap.bit(bit)) {
+            let tick = ((i32::from(word_pos) << 8) + bit as i32) * spacing;
+            let info = pool
+                .ticks(I24::unchecked_from(tick))
+                .block(block_id)
+                .call()
+                .await?;
+            ticks.insert(
+                tick,
+                SnapshotTick {
+                    liquidity_net: info.liquidityNet,
+                    liquidity_gross: info.liquidityGross,
+                    fee_growth_outside0_x128: info.feeGrowthOutside0X128,
+                    fee_growth_outside1_x128: info.feeGrowthOutside1X128,
+                },
+            );
+        }
+    }
+
+    Ok(Snapshot {
+        state_block: block,
+        pool: SnapshotPool {
+            name: String::new(),
+            store: SnapshotPoolStore {
+                version: "v3".to_string(),
+                protocol: "V3Pool".to_string(),
+                fee,
+                tick_spacing,
+                slot0: SnapshotSlot0 {
+                    fee_protocol: slot0.feeProtocol,
+                    tick: slot0.tick.as_i32(),
+                    sqrt_price_x96: U256::from(slot0.sqrtPriceX96),
+                },
+                fee_growth_global0_x128,
+                fee_growth_global1_x128,
+                liquidity,
+                tick_bitmap,
+                ticks,
+                protocol_fees: SnapshotProtocolFees {
+                    token0: protocol_fees.token0,
+                    token1: protocol_fees.token1,
+                },
+                oracle: None,
+                balances: None,
+            },
+            address,
+            token0,
+            token1,
+            protocol: "V3".to_string(),
+            dex: dex.to_string(),
+        },
+    })
+}
+
+/// Same as `fetch_snapshot`, loaded into a pool.
+pub async fn fetch_pool<P: Provider>(
+    provider: &P,
+    address: Address,
+    dex: &str,
+    block: u64,
+    word_radius: i16,
+) -> Result<UniswapV3Pool, FetchError> {
+    let snapshot = fetch_snapshot(provider, address, dex, block, word_radius).await?;
+    Ok(UniswapV3Pool::try_from(&snapshot)?)
+}
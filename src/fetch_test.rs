@@ -0,0 +1,157 @@
+use super::*;
+use crate::fetch::{IUniswapV3Pool, fetch_pool, fetch_snapshot};
+use alloy::providers::ProviderBuilder;
+use alloy::providers::mock::Asserter;
+use alloy::sol_types::SolCall;
+use alloy_primitives::aliases::U160;
+use alloy_primitives::{Bytes, I256};
+use std::str::FromStr;
+
+const POOL: &str = "0xb604d4e46509fe1c1ef70ab4a4941d12a49dbd76";
+const TOKEN0: &str = "0x111111ff85a4ef18c4ed7a3c0e0d7e1e9f2a0c7e";
+const TOKEN1: &str = "0x8d0d000ee44948fc98c9b98a4fa4921476f08b0d";
+
+fn push<C: SolCall>(asserter: &Asserter, ret: &C::Return) {
+    asserter.push_success(&Bytes::from(C::abi_encode_returns(ret)));
+}
+
+fn tick_return(liquidity_gross: u128, liquidity_net: i128) -> IUniswapV3Pool::ticksReturn {
+    IUniswapV3Pool::ticksReturn {
+        liquidityGross: liquidity_gross,
+        liquidityNet: liquidity_net,
+        feeGrowthOutside0X128: U256::from(liquidity_gross),
+        feeGrowthOutside1X128: U256::from(liquidity_gross * 2),
+        tickCumulativeOutside: Default::default(),
+        secondsPerLiquidityOutsideX128: U160::ZERO,
+        secondsOutside: 0,
+        initialized: true,
+    }
+}
+
+// responses in the order the fetcher makes the calls, for a pool at tick -5 with spacing 10
+fn mock_pool(fee_protocol: u32) -> Asserter {
+    use IUniswapV3Pool::*;
+
+    let asserter = Asserter::new();
+    push::<token0Call>(&asserter, &Address::from_str(TOKEN0).unwrap());
+    push::<token1Call>(&asserter, &Address::from_str(TOKEN1).unwrap());
+    push::<feeCall>(&asserter, &U24::from(500));
+    push::<tickSpacingCall>(&asserter, &I24::unchecked_from(10));
+    push::<slot0Call>(
+        &asserter,
+        &slot0Return {
+            sqrtPriceX96: tick_math::get_sqrt_ratio_at_tick(-5).unwrap().to::<U160>(),
+            tick: I24::unchecked_from(-5),
+            observationIndex: 0,
+            observationCardinality: 1,
+            observationCardinalityNext: 1,
+            feeProtocol: fee_protocol,
+            unlocked: true,
+        },
+    );
+    push::<feeGrowthGlobal0X128Call>(&asserter, &U256::from(1000));
+    push::<feeGrowthGlobal1X128Call>(&asserter, &U256::from(2000));
+    push::<protocolFeesCall>(
+        &asserter,
+        &protocolFeesReturn {
+            token0: 3,
+            token1: 4,
+        },
+    );
+    push::<liquidityCall>(&asserter, &3000u128);
+
+    // words -2, -1 and 0, initialized at ticks -2560, -10 and 10
+    push::<tickBitmapCall>(&asserter, &U256::ZERO);
+    push::<tickBitmapCall>(&asserter, &(U256::from(1) | (U256::from(1) << 255)));
+    push::<ticksCall>(&asserter, &tick_return(1000, 1000));
+    push::<ticksCall>(&asserter, &tick_return(2000, 2000));
+    push::<tickBitmapCall>(&asserter, &(U256::from(1) << 1));
+    push::<ticksCall>(&asserter, &tick_return(3000, -3000));
+    asserter
+}
+
+#[tokio::test]
+async fn test_fetch_pool() {
+    let provider = ProviderBuilder::new().connect_mocked_client(mock_pool(0x44));
+    let address = Address::from_str(POOL).unwrap();
+
+    let mut pool = fetch_pool(&provider, address, "Uniswap", 54994241, 1)
+        .await
+        .unwrap();
+    assert_eq!(pool.metadata.address, address);
+    assert_eq!(pool.metadata.state_block, 54994241);
+    assert_eq!(pool.token0, Address::from_str(TOKEN0).unwrap());
+    assert_eq!(pool.fee, U24::from(500));
+    assert_eq!(pool.tick_spacing, I24::unchecked_from(10));
+    assert_eq!(pool.slot0.tick, -5);
+    assert_eq!(pool.metadata.dex, "Uniswap");
+    assert_eq!(pool.slot0.fee_protocol, 0x44);
+    assert_eq!(pool.fee_growth_global1_x128, U256::from(2000));
+    assert_eq!(pool.protocol_fees.token1, 4);
+    assert_eq!(pool.liquidity, 3000);
+
+    assert_eq!(pool.tick_bitmap.len(), 2);
+    let mut ticks: Vec<_> = pool.ticks.keys().copied().collect();
+    ticks.sort();
+    assert_eq!(ticks, vec![-2560, -10, 10]);
+    assert_eq!(pool.ticks[&10].liquidity_net, -3000);
+    assert_eq!(pool.ticks[&-10].fee_growth_outside1_x128, U256::from(4000));
+
+    // the fetched pool can be swapped against
+    let result = pool
+        .swap(SwapParams::exact_input(
+            address,
+            false,
+            I256::from_str("100").unwrap(),
+        ))
+        .unwrap();
+    assert!(result.amount1 > I256::ZERO);
+}
+
+#[tokio::test]
+async fn test_fetch_snapshot_json() {
+    let provider = ProviderBuilder::new().connect_mocked_client(mock_pool(0x44));
+    let address = Address::from_str(POOL).unwrap();
+
+    let snapshot = fetch_snapshot(&provider, address, "Uniswap", 54994241, 1)
+        .await
+        .unwrap();
+    let json = snapshot.to_json_string().unwrap();
+    assert!(json.contains(r#""tick_spacing": "10""#));
+    assert!(json.contains(r#""-2560": {"#));
+
+    let pool = UniswapV3Pool::from_json_str(&json).unwrap();
+    assert_eq!(pool.ticks.len(), 3);
+    assert_eq!(pool.to_snapshot().to_json_string().unwrap(), json);
+}
+
+#[tokio::test]
+async fn test_fetch_error() {
+    let asserter = Asserter::new();
+    asserter.push_failure_msg("header not found");
+    let provider = ProviderBuilder::new().connect_mocked_client(asserter);
+
+    let result = fetch_pool(&provider, Address::from_str(POOL).unwrap(), "Uniswap", 1, 1).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_fetch_pancake_fee_protocol() {
+    // 33% of token0 fees and 25% of token1 fees, in PancakeSwap's packed uint32
+    let fee_protocol = 3300 | (2500 << 16);
+    let provider = ProviderBuilder::new().connect_mocked_client(mock_pool(fee_protocol));
+    let address = Address::from_str(POOL).unwrap();
+
+    let snapshot = fetch_snapshot(&provider, address, "Pancake", 54994241, 1)
+        .await
+        .unwrap();
+    assert_eq!(snapshot.pool.store.slot0.fee_protocol, fee_protocol);
+    let json = snapshot.to_json_string().unwrap();
+    assert!(json.contains(r#""fee_protocol": "0x9c40ce4""#));
+
+    let pool = UniswapV3Pool::from_json_str(&json).unwrap();
+    let layout = pool.metadata.fee_protocol_layout();
+    assert_eq!(layout, FeeProtocolLayout::Pancake);
+    assert_eq!(layout.unpack(pool.slot0.fee_protocol, false), 3300);
+    assert_eq!(layout.unpack(pool.slot0.fee_protocol, true), 2500);
+}
@@ -0,0 +1,154 @@
+use alloy_primitives::{Address, U256};
+
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::callback::{Balances, Journal};
+use crate::ledger::LedgerError;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::full_math;
+
+#[derive(Error, Debug)]
+pub enum FlashError {
+    #[error("flash loan of token0 was not paid back with the fee")]
+    InsufficientPayback0,
+    #[error("flash loan of token1 was not paid back with the fee")]
+    InsufficientPayback1,
+    #[error("transfer failed: {0}")]
+    Ledger(#[from] LedgerError),
+    #[error("flash failed: {0}")]
+    Pool(#[from] UniswapV3MathError),
+}
+
+impl UniswapV3Pool {
+    /// Lends `amount0` and `amount1` and calls `callback` with the fees owed on them. The
+    /// callback returns the amounts paid back, which must cover the amounts lent plus the fees.
+    /// Everything paid above the amounts lent is credited to the liquidity providers, minus the
+    /// protocol fee. Returns the amounts paid above the amounts lent.
+    pub fn flash<F>(
+        &mut self,
+        amount0: U256,
+        amount1: U256,
+        callback: F,
+    ) -> Result<(U256, U256), FlashError>
+    where
+        F: FnOnce(U256, U256) -> (U256, U256),
+    {
+        let liquidity = self.liquidity;
+        if liquidity == 0 {
+            return Err(UniswapV3MathError::LiquidityIsZero.into());
+        }
+
+        let fee = U256::from(self.fee);
+        let fee0 = full_math::mul_div_rounding_up(amount0, fee, U256::from(1_000_000))?;
+        let fee1 = full_math::mul_div_rounding_up(amount1, fee, U256::from(1_000_000))?;
+
+        let (repaid0, repaid1) = callback(fee0, fee1);
+
+        let paid0 = amount0
+            .checked_add(fee0)
+            .filter(|&owed| repaid0 >= owed)
+            .map(|_| repaid0 - amount0)
+            .ok_or(FlashError::InsufficientPayback0)?;
+        let paid1 = amount1
+            .checked_add(fee1)
+            .filter(|&owed| repaid1 >= owed)
+            .map(|_| repaid1 - amount1)
+            .ok_or(FlashError::InsufficientPayback1)?;
+
+        let layout = self.metadata.fee_protocol_layout();
+        if paid0 > U256::ZERO {
+            let fee_protocol0 = layout.unpack(self.slot0.fee_protocol, false);
+            let fees0 = layout.protocol_fee(fee_protocol0, paid0);
+            self.fee_growth_global0_x128 =
+                self.fee_growth_global0_x128
+                    .wrapping_add(full_math::mul_div(
+                        paid0 - fees0,
+                        full_math::Q128,
+                        U256::from(liquidity),
+                    )?);
+            self.protocol_fees.token0 = self
+                .protocol_fees
+                .token0
+                .wrapping_add(fees0.wrapping_to::<u128>());
+        }
+        if paid1 > U256::ZERO {
+            let fee_protocol1 = layout.unpack(self.slot0.fee_protocol, true);
+            let fees1 = layout.protocol_fee(fee_protocol1, paid1);
+            self.fee_growth_global1_x128 =
+                self.fee_growth_global1_x128
+                    .wrapping_add(full_math::mul_div(
+                        paid1 - fees1,
+                        full_math::Q128,
+                        U256::from(liquidity),
+                    )?);
+            self.protocol_fees.token1 = self
+                .protocol_fees
+                .token1
+                .wrapping_add(fees1.wrapping_to::<u128>());
+        }
+
+        Ok((paid0, paid1))
+    }
+
+    /// Same as `flash`, with the loan settled in `balances` as the contract does: the amounts
+    /// are transferred from the pool to `recipient` before `callback` is called with the fees
+    /// owed, and the pool's balances must have grown by the fees afterwards. On error the
+    /// transfers made so far are reverted, leaving both the pool and `balances` unchanged.
+    pub fn flash_with_balances<F>(
+        &mut self,
+        balances: &mut dyn Balances,
+        recipient: Address,
+        amount0: U256,
+        amount1: U256,
+        callback: F,
+    ) -> Result<(U256, U256), FlashError>
+    where
+        F: FnOnce(&mut dyn Balances, U256, U256) -> Result<(), FlashError>,
+    {
+        if self.liquidity == 0 {
+            return Err(UniswapV3MathError::LiquidityIsZero.into());
+        }
+
+        let pool = self.metadata.address;
+        let (token0, token1) = (self.token0, self.token1);
+        let mut journal = Journal::new(balances);
+        let balances = &mut journal;
+        let balance0_before = balances.balance_of(token0, pool);
+        let balance1_before = balances.balance_of(token1, pool);
+        let lent = [(token0, amount0), (token1, amount1)]
+            .into_iter()
+            .filter(|&(_, amount)| amount > U256::ZERO)
+            .try_for_each(|(token, amount)| balances.transfer(token, pool, recipient, amount));
+        if let Err(err) = lent {
+            journal.revert();
+            return Err(err.into());
+        }
+
+        let mut callback_error = None;
+        let paid = self.flash(amount0, amount1, |fee0, fee1| {
+            if let Err(err) = callback(balances, fee0, fee1) {
+                callback_error = Some(err);
+                return (U256::ZERO, U256::ZERO);
+            }
+            // what came back on top of what was left after lending
+            let repaid = |token, balance_before: U256, amount| {
+                balances
+                    .balance_of(token, pool)
+                    .saturating_sub(balance_before - amount)
+            };
+            (
+                repaid(token0, balance0_before, amount0),
+                repaid(token1, balance1_before, amount1),
+            )
+        });
+        let paid = match callback_error {
+            Some(err) => Err(err),
+            None => paid,
+        };
+        if paid.is_err() {
+            journal.revert();
+        }
+        paid
+    }
+}
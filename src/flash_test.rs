@@ -0,0 +1,115 @@
+use super::*;
+use crate::flash::FlashError;
+use crate::test_utils::{SNAPSHOT, u256};
+
+#[test]
+fn test_flash_fee_growth() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let fee_growth_global1_x128 = pool.fee_growth_global1_x128;
+
+    let (paid0, paid1) = pool
+        .flash(U256::from(10u128.pow(21)), U256::ZERO, |fee0, fee1| {
+            assert_eq!(fee0, U256::from(10u128.pow(17)));
+            assert_eq!(fee1, U256::ZERO);
+            (U256::from(10u128.pow(21)) + fee0, U256::ZERO)
+        })
+        .unwrap();
+
+    assert_eq!((paid0, paid1), (U256::from(10u128.pow(17)), U256::ZERO));
+    assert_eq!(
+        pool.fee_growth_global0_x128,
+        u256("39906484841094046634404578036854")
+    );
+    assert_eq!(pool.fee_growth_global1_x128, fee_growth_global1_x128);
+    assert_eq!(pool.protocol_fees.token0, 0);
+}
+
+#[test]
+fn test_flash_protocol_fee() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.metadata.dex = "Uniswap".to_string();
+    pool.slot0.fee_protocol = 0x64;
+
+    // paying back more than the fee donates the excess
+    let amount0 = U256::from(10u128.pow(21));
+    let amount1 = U256::from(5 * 10u128.pow(20));
+    let (paid0, paid1) = pool
+        .flash(amount0, amount1, |fee0, fee1| {
+            (amount0 + fee0, amount1 + fee1 + U256::from(1000))
+        })
+        .unwrap();
+
+    assert_eq!(paid0, U256::from(10u128.pow(17)));
+    assert_eq!(paid1, U256::from(50000000000001000u128));
+    assert_eq!(pool.protocol_fees.token0, 25000000000000000);
+    assert_eq!(pool.protocol_fees.token1, 8333333333333500);
+    assert_eq!(
+        pool.fee_growth_global0_x128,
+        u256("30279955064840343871009254472674")
+    );
+    assert_eq!(
+        pool.fee_growth_global1_x128,
+        u256("16539384130391523623217395401821")
+    );
+}
+
+#[test]
+fn test_flash_pancake_protocol_fee() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    // 33% of token0 fees and 25% of token1 fees, in basis points
+    pool.slot0.fee_protocol = 3300 | (2500 << 16);
+    let fee_growth_global0_x128 = pool.fee_growth_global0_x128;
+    let liquidity = U256::from(pool.liquidity);
+
+    let amount = U256::from(10u128.pow(21));
+    let (paid0, paid1) = pool
+        .flash(amount, amount, |fee0, fee1| (amount + fee0, amount + fee1))
+        .unwrap();
+
+    assert_eq!(
+        (paid0, paid1),
+        (U256::from(10u128.pow(17)), U256::from(10u128.pow(17)))
+    );
+    assert_eq!(pool.protocol_fees.token0, 33000000000000000);
+    assert_eq!(pool.protocol_fees.token1, 25000000000000000);
+    assert_eq!(
+        pool.fee_growth_global0_x128,
+        fee_growth_global0_x128
+            + full_math::mul_div(
+                U256::from(67000000000000000u128),
+                full_math::Q128,
+                liquidity
+            )
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_flash_insufficient_payback() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let fee_growth_global0_x128 = pool.fee_growth_global0_x128;
+    let amount = U256::from(10u128.pow(21));
+
+    let result = pool.flash(amount, U256::ZERO, |fee0, _| {
+        (amount + fee0 - U256::from(1), U256::ZERO)
+    });
+    assert!(matches!(result, Err(FlashError::InsufficientPayback0)));
+
+    let result = pool.flash(U256::ZERO, amount, |_, _| (U256::ZERO, amount));
+    assert!(matches!(result, Err(FlashError::InsufficientPayback1)));
+    assert_eq!(pool.fee_growth_global0_x128, fee_growth_global0_x128);
+}
+
+#[test]
+fn test_flash_without_liquidity() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.liquidity = 0;
+
+    let result = pool.flash(U256::from(1), U256::ZERO, |fee0, _| {
+        (U256::from(1) + fee0, U256::ZERO)
+    });
+    assert!(matches!(
+        result,
+        Err(FlashError::Pool(UniswapV3MathError::LiquidityIsZero))
+    ));
+}
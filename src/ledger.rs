@@ -0,0 +1,75 @@
+use alloy_primitives::{Address, U256};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::callback::Balances;
+use crate::libraries::error::UniswapV3MathError;
+use crate::snapshot::Snapshot;
+
+#[derive(Error, Debug)]
+pub enum LedgerError {
+    #[error("insufficient balance of token {token} held by {holder}")]
+    InsufficientBalance { token: Address, holder: Address },
+    #[error("pool error: {0}")]
+    Pool(#[from] UniswapV3MathError),
+}
+
+/// ERC20 balances keyed by token and holder, for pools as well as accounts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ledger {
+    balances: HashMap<(Address, Address), U256>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_balance(&mut self, token: Address, holder: Address, amount: U256) {
+        self.balances.insert((token, holder), amount);
+    }
+
+    /// Adds `amount` to the balance of `holder`, as if it was minted to them.
+    pub fn credit(&mut self, token: Address, holder: Address, amount: U256) {
+        let balance = self.balances.entry((token, holder)).or_default();
+        *balance = balance.saturating_add(amount);
+    }
+
+    /// Sets the pool's reserves to the balances recorded in `snapshot`. Snapshots without
+    /// balances leave the ledger unchanged.
+    pub fn seed(&mut self, snapshot: &Snapshot) {
+        let pool = &snapshot.pool;
+        if let Some(balances) = &pool.store.balances {
+            self.set_balance(pool.token0, pool.address, balances.token0);
+            self.set_balance(pool.token1, pool.address, balances.token1);
+        }
+    }
+}
+
+impl Balances for Ledger {
+    fn balance_of(&self, token: Address, holder: Address) -> U256 {
+        self.balances
+            .get(&(token, holder))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn transfer(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), LedgerError> {
+        let balance = self.balance_of(token, from);
+        if balance < amount {
+            return Err(LedgerError::InsufficientBalance {
+                token,
+                holder: from,
+            });
+        }
+        self.balances.insert((token, from), balance - amount);
+        self.credit(token, to, amount);
+        Ok(())
+    }
+}
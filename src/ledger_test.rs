@@ -0,0 +1,242 @@
+use super::*;
+use crate::callback::{Balances, CallbackError};
+use crate::flash::FlashError;
+use crate::ledger::{Ledger, LedgerError};
+use crate::snapshot::{Snapshot, SnapshotBalances};
+use crate::test_utils::{SNAPSHOT, address, i256, owner};
+use alloy_primitives::Bytes;
+
+fn setup(reserve: U256) -> (UniswapV3Pool, Ledger) {
+    let mut snapshot = Snapshot::from_json_file(SNAPSHOT).unwrap();
+    snapshot.pool.store.balances = Some(SnapshotBalances {
+        token0: reserve,
+        token1: reserve,
+    });
+    let pool = UniswapV3Pool::try_from(&snapshot).unwrap();
+    let mut ledger = Ledger::new();
+    ledger.seed(&snapshot);
+    for token in [pool.token0, pool.token1] {
+        ledger.credit(token, owner(), U256::from(10u128.pow(24)));
+    }
+    (pool, ledger)
+}
+
+#[test]
+fn test_seed_from_snapshot() {
+    let (pool, ledger) = setup(U256::from(10u128.pow(22)));
+    assert_eq!(
+        ledger.balance_of(pool.token0, pool.metadata.address),
+        U256::from(10u128.pow(22))
+    );
+    assert_eq!(
+        ledger.balance_of(pool.token1, pool.metadata.address),
+        U256::from(10u128.pow(22))
+    );
+
+    // snapshots without balances seed nothing
+    let mut ledger = Ledger::new();
+    ledger.seed(&Snapshot::from_json_file(SNAPSHOT).unwrap());
+    assert_eq!(ledger, Ledger::new());
+}
+
+#[test]
+fn test_snapshot_balances_round_trip() {
+    let mut snapshot = Snapshot::from_json_file(SNAPSHOT).unwrap();
+    snapshot.pool.store.balances = Some(SnapshotBalances {
+        token0: U256::from(0x1234),
+        token1: U256::ZERO,
+    });
+
+    let json = snapshot.to_json_string().unwrap();
+    assert!(json.contains(r#""token0": "0x1234""#));
+    let loaded = Snapshot::from_json_str(&json).unwrap();
+    let balances = loaded.pool.store.balances.unwrap();
+    assert_eq!(balances.token0, U256::from(0x1234));
+    assert_eq!(balances.token1, U256::ZERO);
+}
+
+#[test]
+fn test_transfer() {
+    let token = address("0x0000000000000000000000000000000000000001");
+    let mut ledger = Ledger::new();
+    ledger.set_balance(token, owner(), U256::from(100));
+
+    ledger
+        .transfer(token, owner(), Address::ZERO, U256::from(60))
+        .unwrap();
+    assert_eq!(ledger.balance_of(token, owner()), U256::from(40));
+    assert_eq!(ledger.balance_of(token, Address::ZERO), U256::from(60));
+
+    let result = ledger.transfer(token, owner(), Address::ZERO, U256::from(41));
+    assert!(matches!(
+        result,
+        Err(LedgerError::InsufficientBalance { token: t, holder })
+            if t == token && holder == owner()
+    ));
+    assert_eq!(ledger.balance_of(token, owner()), U256::from(40));
+}
+
+#[test]
+fn test_swap_exceeds_reserves() {
+    let (mut pool, mut ledger) = setup(U256::from(10u128.pow(18)));
+    let token1 = pool.token1;
+    let params = SwapParams::exact_input(owner(), true, i256("1000000000000000000000"));
+    let mut callback =
+        |balances: &mut dyn Balances, pool: Address, _: I256, amount1_delta: I256, _: &Bytes| {
+            Ok(balances.transfer(token1, owner(), pool, amount1_delta.into_raw())?)
+        };
+
+    let result = pool.swap_with_callback(params, &mut ledger, &mut callback);
+    assert!(matches!(
+        result,
+        Err(CallbackError::Ledger(LedgerError::InsufficientBalance { holder, .. }))
+            if holder == pool.metadata.address
+    ));
+}
+
+#[test]
+fn test_mint_burn_and_collect() {
+    let (mut pool, mut ledger) = setup(U256::ZERO);
+    let pool_address = pool.metadata.address;
+
+    let (amount0, amount1) = pool
+        .mint_paid_by(&mut ledger, owner(), owner(), 20000, 21000, 10u128.pow(21))
+        .unwrap();
+    assert_eq!(ledger.balance_of(pool.token0, pool_address), amount0);
+    assert_eq!(ledger.balance_of(pool.token1, pool_address), amount1);
+    assert_eq!(
+        ledger.balance_of(pool.token0, owner()),
+        U256::from(10u128.pow(24)) - amount0
+    );
+
+    // burning leaves the tokens in the pool until they are collected
+    let (burned0, burned1) = pool.burn(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    assert_eq!(ledger.balance_of(pool.token0, pool_address), amount0);
+
+    let recipient = address("0x0000000000000000000000000000000000000002");
+    let (collected0, collected1) = pool
+        .collect_to(
+            &mut ledger,
+            recipient,
+            owner(),
+            20000,
+            21000,
+            u128::MAX,
+            u128::MAX,
+        )
+        .unwrap();
+    assert_eq!(
+        (U256::from(collected0), U256::from(collected1)),
+        (burned0, burned1)
+    );
+    assert_eq!(ledger.balance_of(pool.token0, recipient), burned0);
+    assert_eq!(ledger.balance_of(pool.token1, recipient), burned1);
+    // burning rounds down, leaving dust in the pool
+    assert_eq!(
+        ledger.balance_of(pool.token0, pool_address),
+        amount0 - burned0
+    );
+}
+
+#[test]
+fn test_mint_insufficient_balance() {
+    let (mut pool, mut ledger) = setup(U256::ZERO);
+    let liquidity = pool.liquidity;
+    let payer = address("0x0000000000000000000000000000000000000003");
+    ledger.credit(pool.token0, payer, U256::from(10u128.pow(24)));
+
+    let result = pool.mint_paid_by(&mut ledger, payer, payer, 20000, 21000, 10u128.pow(21));
+    assert!(matches!(
+        result,
+        Err(LedgerError::InsufficientBalance { token, holder })
+            if token == pool.token1 && holder == payer
+    ));
+    assert_eq!(pool.liquidity, liquidity);
+    assert!(pool.positions.is_empty());
+    assert!(!pool.ticks.contains_key(&20000));
+    assert_eq!(
+        ledger.balance_of(pool.token0, payer),
+        U256::from(10u128.pow(24))
+    );
+}
+
+#[test]
+fn test_collect_exceeds_reserves() {
+    let (mut pool, mut ledger) = setup(U256::ZERO);
+    pool.mint(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    pool.burn(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    let owed = pool.positions[&(owner(), 20000, 21000)].clone();
+
+    let result = pool.collect_to(
+        &mut ledger,
+        owner(),
+        owner(),
+        20000,
+        21000,
+        u128::MAX,
+        u128::MAX,
+    );
+    assert!(matches!(
+        result,
+        Err(LedgerError::InsufficientBalance { .. })
+    ));
+    assert_eq!(pool.positions[&(owner(), 20000, 21000)], owed);
+}
+
+#[test]
+fn test_flash_with_balances() {
+    let (mut pool, mut ledger) = setup(U256::from(10u128.pow(22)));
+    let (token0, pool_address) = (pool.token0, pool.metadata.address);
+    let amount = U256::from(10u128.pow(21));
+
+    let (paid0, paid1) = pool
+        .flash_with_balances(
+            &mut ledger,
+            owner(),
+            amount,
+            U256::ZERO,
+            |balances, fee0, _| {
+                Ok(balances.transfer(token0, owner(), pool_address, amount + fee0)?)
+            },
+        )
+        .unwrap();
+    assert_eq!((paid0, paid1), (U256::from(10u128.pow(17)), U256::ZERO));
+    assert_eq!(
+        ledger.balance_of(token0, pool_address),
+        U256::from(10u128.pow(22)) + paid0
+    );
+
+    // only the principal is paid back, and the loan is reverted
+    let ledger_before = ledger.clone();
+    let result = pool.flash_with_balances(
+        &mut ledger,
+        owner(),
+        amount,
+        U256::ZERO,
+        |balances, _, _| Ok(balances.transfer(token0, owner(), pool_address, amount)?),
+    );
+    assert!(matches!(result, Err(FlashError::InsufficientPayback0)));
+    assert_eq!(ledger, ledger_before);
+
+    let token1 = pool.token1;
+    let result =
+        pool.flash_with_balances(&mut ledger, owner(), amount, amount, |balances, fee0, _| {
+            balances.transfer(token0, owner(), pool_address, amount + fee0)?;
+            Ok(balances.transfer(token1, owner(), pool_address, amount)?)
+        });
+    assert!(matches!(result, Err(FlashError::InsufficientPayback1)));
+    assert_eq!(ledger, ledger_before);
+
+    // the pool can't lend more than it holds
+    let result = pool.flash_with_balances(
+        &mut ledger,
+        owner(),
+        U256::from(10u128.pow(23)),
+        U256::ZERO,
+        |_, _, _| Ok(()),
+    );
+    assert!(matches!(
+        result,
+        Err(FlashError::Ledger(LedgerError::InsufficientBalance { .. }))
+    ));
+}
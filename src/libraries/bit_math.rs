@@ -0,0 +1,76 @@
+use alloy_primitives::U256;
+
+use crate::libraries::error::UniswapV3MathError;
+
+pub fn most_significant_bit(x: U256) -> Result<u8, UniswapV3MathError> {
+    if x.is_zero() {
+        return Err(UniswapV3MathError::ZeroValue);
+    }
+
+    Ok(255 - x.leading_zeros() as u8)
+}
+
+pub fn least_significant_bit(x: U256) -> Result<u8, UniswapV3MathError> {
+    if x.is_zero() {
+        return Err(UniswapV3MathError::ZeroValue);
+    }
+
+    Ok(x.trailing_zeros() as u8)
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_primitives::U256;
+
+    use crate::libraries::U256_1;
+
+    use super::{least_significant_bit, most_significant_bit};
+
+    #[test]
+    fn test_most_significant_bit() {
+        //0
+        let result = most_significant_bit(U256::ZERO);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Can not get most significant bit or least significant bit on zero value"
+        );
+
+        //1
+        assert_eq!(most_significant_bit(U256_1).unwrap(), 0);
+
+        //2
+        assert_eq!(most_significant_bit(U256::from(2)).unwrap(), 1);
+
+        //all powers of 2
+        for i in 0..=255 {
+            assert_eq!(most_significant_bit(U256_1 << i).unwrap(), i as u8);
+        }
+
+        //uint256(-1)
+        assert_eq!(most_significant_bit(U256::MAX).unwrap(), 255);
+    }
+
+    #[test]
+    fn test_least_significant_bit() {
+        //0
+        let result = least_significant_bit(U256::ZERO);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Can not get most significant bit or least significant bit on zero value"
+        );
+
+        //1
+        assert_eq!(least_significant_bit(U256_1).unwrap(), 0);
+
+        //2
+        assert_eq!(least_significant_bit(U256::from(2)).unwrap(), 1);
+
+        //all powers of 2
+        for i in 0..=255 {
+            assert_eq!(least_significant_bit(U256_1 << i).unwrap(), i as u8);
+        }
+
+        //uint256(-1)
+        assert_eq!(least_significant_bit(U256::MAX).unwrap(), 0);
+    }
+}
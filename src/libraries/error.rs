@@ -36,8 +36,26 @@ pub enum UniswapV3MathError {
     R,
     #[error("Overflow when casting to U160")]
     SafeCastToU160Overflow,
+    #[error("Overflow when casting to u128")]
+    SafeCastToU128Overflow,
+    #[error("Overflow when casting to i128")]
+    SafeCastToI128Overflow,
     #[error("Tick spacing error")]
     TickSpacingError,
+    #[error("Tick lower must be less than tick upper")]
+    TickLowerNotBelowUpper,
+    #[error("Tick lower is less than the minimum tick")]
+    TickLowerTooLow,
+    #[error("Tick upper is greater than the maximum tick")]
+    TickUpperTooHigh,
+    #[error("Liquidity gross exceeds the max liquidity per tick")]
+    LiquidityGrossOverflow,
+    #[error("Cannot poke a position without liquidity")]
+    NoLiquidityPosition,
+    #[error("Oracle is not initialized")]
+    OracleNotInitialized,
+    #[error("Target is older than the oldest observation")]
+    ObservationTooOld,
     #[error("Middleware error when getting next_initialized_tick_within_one_word")]
     MiddlewareError(String),
     #[error("Parse error")]
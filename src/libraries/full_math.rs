@@ -4,6 +4,8 @@ use alloy_primitives::U256;
 
 use crate::libraries::{U256_1, U256_2, U256_3, error::UniswapV3MathError};
 
+pub const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
 pub fn mul_div(a: U256, b: U256, mut denominator: U256) -> Result<U256, UniswapV3MathError> {
     let mm = a.mul_mod(b, U256::MAX);
 
@@ -0,0 +1,241 @@
+use alloy_primitives::U256;
+
+use crate::libraries::{error::UniswapV3MathError, full_math::mul_div, sqrt_price_math::Q96};
+
+fn to_u128(x: U256) -> Result<u128, UniswapV3MathError> {
+    u128::try_from(x).map_err(|_| UniswapV3MathError::SafeCastToU128Overflow)
+}
+
+fn sort(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256) -> (U256, U256) {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    } else {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96)
+    }
+}
+
+pub fn get_liquidity_for_amount0(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount0: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    let intermediate = mul_div(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, Q96)?;
+    to_u128(mul_div(
+        amount0,
+        intermediate,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+pub fn get_liquidity_for_amount1(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    to_u128(mul_div(
+        amount1,
+        Q96,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+pub fn get_liquidity_for_amounts(
+    sqrt_ratio_x_96: U256,
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount0: U256,
+    amount1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        get_liquidity_for_amount0(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount0)
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        let liquidity0 = get_liquidity_for_amount0(sqrt_ratio_x_96, sqrt_ratio_b_x_96, amount0)?;
+        let liquidity1 = get_liquidity_for_amount1(sqrt_ratio_a_x_96, sqrt_ratio_x_96, amount1)?;
+
+        Ok(liquidity0.min(liquidity1))
+    } else {
+        get_liquidity_for_amount1(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount1)
+    }
+}
+
+pub fn get_amount0_for_liquidity(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<U256, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    mul_div(
+        U256::from(liquidity) << 96,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+        sqrt_ratio_b_x_96,
+    )?
+    .checked_div(sqrt_ratio_a_x_96)
+    .ok_or(UniswapV3MathError::SqrtPriceIsZero)
+}
+
+pub fn get_amount1_for_liquidity(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<U256, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    mul_div(
+        U256::from(liquidity),
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+        Q96,
+    )
+}
+
+pub fn get_amounts_for_liquidity(
+    sqrt_ratio_x_96: U256,
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        Ok((
+            get_amount0_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity)?,
+            U256::ZERO,
+        ))
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        Ok((
+            get_amount0_for_liquidity(sqrt_ratio_x_96, sqrt_ratio_b_x_96, liquidity)?,
+            get_amount1_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_x_96, liquidity)?,
+        ))
+    } else {
+        Ok((
+            U256::ZERO,
+            get_amount1_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use alloy_primitives::U256;
+
+    use crate::libraries::error::UniswapV3MathError;
+    use crate::libraries::sqrt_price_math::_get_amount_0_delta;
+
+    use super::{
+        get_amount0_for_liquidity, get_amounts_for_liquidity, get_liquidity_for_amount0,
+        get_liquidity_for_amount1, get_liquidity_for_amounts,
+    };
+
+    // encodePriceSqrt(1, 1), (100, 110), (110, 100), (99, 110) and (111, 100)
+    fn prices() -> [U256; 5] {
+        [
+            "79228162514264337593543950336",
+            "75541088972021052632782079082",
+            "83095197869223157896060286990",
+            "75162434512514379355924140470",
+            "83472048772503575395058907992",
+        ]
+        .map(|price| U256::from_str(price).unwrap())
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amounts() {
+        let [price, sqrt_price_a, sqrt_price_b, below, above] = prices();
+        let (amount0, amount1) = (U256::from(100), U256::from(200));
+
+        //amounts for price inside
+        let liquidity =
+            get_liquidity_for_amounts(price, sqrt_price_a, sqrt_price_b, amount0, amount1).unwrap();
+        assert_eq!(liquidity, 2148);
+
+        //amounts for price below
+        let liquidity =
+            get_liquidity_for_amounts(below, sqrt_price_a, sqrt_price_b, amount0, amount1).unwrap();
+        assert_eq!(liquidity, 1048);
+
+        //amounts for price above
+        let liquidity =
+            get_liquidity_for_amounts(above, sqrt_price_a, sqrt_price_b, amount0, amount1).unwrap();
+        assert_eq!(liquidity, 2097);
+
+        //amounts for price equal to lower boundary
+        let liquidity =
+            get_liquidity_for_amounts(sqrt_price_a, sqrt_price_a, sqrt_price_b, amount0, amount1)
+                .unwrap();
+        assert_eq!(liquidity, 1048);
+
+        //amounts for price equal to upper boundary
+        let liquidity =
+            get_liquidity_for_amounts(sqrt_price_b, sqrt_price_a, sqrt_price_b, amount0, amount1)
+                .unwrap();
+        assert_eq!(liquidity, 2097);
+
+        //the order of the boundaries does not matter
+        let liquidity =
+            get_liquidity_for_amounts(price, sqrt_price_b, sqrt_price_a, amount0, amount1).unwrap();
+        assert_eq!(liquidity, 2148);
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amount() {
+        let [_, sqrt_price_a, sqrt_price_b, _, _] = prices();
+
+        //fails if the boundaries are equal
+        let result = get_liquidity_for_amount1(sqrt_price_a, sqrt_price_a, U256::from(1));
+        assert_eq!(result.unwrap_err().to_string(), "Denominator is 0");
+
+        //fails if the liquidity does not fit into 128 bits
+        let result = get_liquidity_for_amount0(sqrt_price_a, sqrt_price_b, U256::MAX >> 64);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to u128"
+        );
+    }
+
+    #[test]
+    fn test_get_amounts_for_liquidity() {
+        let [price, sqrt_price_a, sqrt_price_b, below, above] = prices();
+
+        //amounts for price inside
+        let amounts = get_amounts_for_liquidity(price, sqrt_price_a, sqrt_price_b, 2148).unwrap();
+        assert_eq!(amounts, (U256::from(99), U256::from(99)));
+
+        //amounts for price below
+        let amounts = get_amounts_for_liquidity(below, sqrt_price_a, sqrt_price_b, 1048).unwrap();
+        assert_eq!(amounts, (U256::from(99), U256::ZERO));
+
+        //amounts for price above
+        let amounts = get_amounts_for_liquidity(above, sqrt_price_a, sqrt_price_b, 2097).unwrap();
+        assert_eq!(amounts, (U256::ZERO, U256::from(199)));
+
+        //amounts for price on lower boundary
+        let amounts =
+            get_amounts_for_liquidity(sqrt_price_a, sqrt_price_a, sqrt_price_b, 1048).unwrap();
+        assert_eq!(amounts, (U256::from(99), U256::ZERO));
+
+        //amounts for price on upper boundary
+        let amounts =
+            get_amounts_for_liquidity(sqrt_price_b, sqrt_price_a, sqrt_price_b, 2097).unwrap();
+        assert_eq!(amounts, (U256::ZERO, U256::from(199)));
+
+        //rounds down, unlike the amounts owed when minting
+        let amount0 = get_amount0_for_liquidity(sqrt_price_a, sqrt_price_b, 2148).unwrap();
+        assert_eq!(amount0, U256::from(204));
+        assert_eq!(
+            _get_amount_0_delta(sqrt_price_a, sqrt_price_b, 2148, true).unwrap(),
+            U256::from(205)
+        );
+
+        //a zero price is an error rather than a division by zero
+        let result = get_amount0_for_liquidity(U256::ZERO, sqrt_price_b, 2148);
+        assert!(matches!(result, Err(UniswapV3MathError::SqrtPriceIsZero)));
+    }
+}
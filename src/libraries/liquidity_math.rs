@@ -0,0 +1,43 @@
+use crate::libraries::error::UniswapV3MathError;
+
+pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
+    if y < 0 {
+        x.checked_sub(y.unsigned_abs())
+            .ok_or(UniswapV3MathError::LiquiditySub)
+    } else {
+        x.checked_add(y as u128)
+            .ok_or(UniswapV3MathError::LiquidityAdd)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::add_delta;
+
+    #[test]
+    fn test_add_delta() {
+        //1 + 0
+        assert_eq!(add_delta(1, 0).unwrap(), 1);
+
+        //1 + -1
+        assert_eq!(add_delta(1, -1).unwrap(), 0);
+
+        //1 + 1
+        assert_eq!(add_delta(1, 1).unwrap(), 2);
+
+        //2**128-15 + 15 overflows
+        let result = add_delta(u128::MAX - 14, 15);
+        assert_eq!(result.unwrap_err().to_string(), "Liquidity Add");
+
+        //0 + -1 underflows
+        let result = add_delta(0, -1);
+        assert_eq!(result.unwrap_err().to_string(), "Liquidity Sub");
+
+        //3 + -4 underflows
+        let result = add_delta(3, -4);
+        assert_eq!(result.unwrap_err().to_string(), "Liquidity Sub");
+
+        //i128::MIN does not overflow the negation
+        assert_eq!(add_delta(u128::MAX, i128::MIN).unwrap(), u128::MAX >> 1);
+    }
+}
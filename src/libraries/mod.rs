@@ -1,9 +1,16 @@
 use alloy_primitives::U256;
 
+pub mod bit_math;
 pub mod error;
 pub mod full_math;
+pub mod liquidity_amounts;
+pub mod liquidity_math;
+pub mod oracle;
+pub mod position;
 pub mod sqrt_price_math;
 pub mod swap_math;
+pub mod tick;
+pub mod tick_bitmap;
 pub mod tick_math;
 pub mod unsafe_math;
 
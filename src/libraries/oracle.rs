@@ -0,0 +1,539 @@
+use alloy_primitives::U256;
+
+use crate::libraries::error::UniswapV3MathError;
+use crate::pool_data::Observation;
+
+const MAX_U160: U256 = U256::from_limbs([u64::MAX, u64::MAX, u32::MAX as u64, 0]);
+
+// int56 arithmetic wraps at 56 bits
+fn wrap_i56(x: i64) -> i64 {
+    (x << 8) >> 8
+}
+
+fn transform(last: &Observation, block_timestamp: u32, tick: i32, liquidity: u128) -> Observation {
+    let delta = block_timestamp.wrapping_sub(last.block_timestamp);
+    let liquidity = if liquidity > 0 { liquidity } else { 1 };
+
+    Observation {
+        block_timestamp,
+        tick_cumulative: wrap_i56(
+            last.tick_cumulative
+                .wrapping_add((tick as i64).wrapping_mul(delta as i64)),
+        ),
+        seconds_per_liquidity_cumulative_x128: last
+            .seconds_per_liquidity_cumulative_x128
+            .wrapping_add((U256::from(delta) << 128) / U256::from(liquidity))
+            & MAX_U160,
+        initialized: true,
+    }
+}
+
+pub fn initialize(observations: &mut Vec<Observation>, time: u32) -> (u16, u16) {
+    let first = Observation {
+        block_timestamp: time,
+        tick_cumulative: 0,
+        seconds_per_liquidity_cumulative_x128: U256::ZERO,
+        initialized: true,
+    };
+    match observations.first_mut() {
+        Some(observation) => *observation = first,
+        None => observations.push(first),
+    }
+
+    (1, 1)
+}
+
+pub fn write(
+    observations: &mut [Observation],
+    index: u16,
+    block_timestamp: u32,
+    tick: i32,
+    liquidity: u128,
+    cardinality: u16,
+    cardinality_next: u16,
+) -> (u16, u16) {
+    let last = &observations[index as usize];
+
+    // early return if we've already written an observation this block
+    if last.block_timestamp == block_timestamp {
+        return (index, cardinality);
+    }
+
+    // if the conditions are right, we can bump the cardinality
+    let cardinality_updated = if cardinality_next > cardinality && index == cardinality - 1 {
+        cardinality_next
+    } else {
+        cardinality
+    };
+
+    let index_updated = (index + 1) % cardinality_updated;
+    observations[index_updated as usize] = transform(last, block_timestamp, tick, liquidity);
+
+    (index_updated, cardinality_updated)
+}
+
+pub fn grow(
+    observations: &mut Vec<Observation>,
+    current: u16,
+    next: u16,
+) -> Result<u16, UniswapV3MathError> {
+    if current == 0 {
+        return Err(UniswapV3MathError::OracleNotInitialized);
+    }
+
+    // no-op if the passed next value isn't greater than the current next value
+    if next <= current {
+        return Ok(current);
+    }
+
+    // store in each slot to prevent fresh SSTOREs in swaps
+    // this data will not be used because the initialized boolean is still false
+    observations.resize(
+        observations.len().max(next as usize),
+        Observation::default(),
+    );
+    for observation in &mut observations[current as usize..next as usize] {
+        observation.block_timestamp = 1;
+    }
+
+    Ok(next)
+}
+
+// comparator for 32-bit timestamps that are at most 2**32 - 1 seconds apart from `time`
+fn lte(time: u32, a: u32, b: u32) -> bool {
+    // if there hasn't been overflow, no need to adjust
+    if a <= time && b <= time {
+        return a <= b;
+    }
+
+    let a_adjusted = if a > time {
+        a as u64
+    } else {
+        a as u64 + (1 << 32)
+    };
+    let b_adjusted = if b > time {
+        b as u64
+    } else {
+        b as u64 + (1 << 32)
+    };
+
+    a_adjusted <= b_adjusted
+}
+
+fn binary_search(
+    observations: &[Observation],
+    time: u32,
+    target: u32,
+    index: u16,
+    cardinality: u16,
+) -> (Observation, Observation) {
+    let cardinality = cardinality as usize;
+    let mut l = (index as usize + 1) % cardinality; // oldest observation
+    let mut r = l + cardinality - 1; // newest observation
+
+    loop {
+        let i = (l + r) / 2;
+
+        let before_or_at = &observations[i % cardinality];
+
+        // we've landed on an uninitialized tick, keep searching higher (more recently)
+        if !before_or_at.initialized {
+            l = i + 1;
+            continue;
+        }
+
+        let at_or_after = &observations[(i + 1) % cardinality];
+
+        let target_at_or_after = lte(time, before_or_at.block_timestamp, target);
+
+        // check if we've found the answer!
+        if target_at_or_after && lte(time, target, at_or_after.block_timestamp) {
+            return (before_or_at.clone(), at_or_after.clone());
+        }
+
+        if !target_at_or_after {
+            r = i - 1;
+        } else {
+            l = i + 1;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn get_surrounding_observations(
+    observations: &[Observation],
+    time: u32,
+    target: u32,
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(Observation, Observation), UniswapV3MathError> {
+    // optimistically set before to the newest observation
+    let before_or_at = &observations[index as usize];
+
+    // if the target is chronologically at or after the newest observation, we can early return
+    if lte(time, before_or_at.block_timestamp, target) {
+        if before_or_at.block_timestamp == target {
+            // if newest observation equals target, we're in the same block, so we can ignore atOrAfter
+            return Ok((before_or_at.clone(), Observation::default()));
+        } else {
+            // otherwise, we need to transform
+            return Ok((
+                before_or_at.clone(),
+                transform(before_or_at, target, tick, liquidity),
+            ));
+        }
+    }
+
+    // now, set before to the oldest observation
+    let mut before_or_at = &observations[(index as usize + 1) % cardinality as usize];
+    if !before_or_at.initialized {
+        before_or_at = &observations[0];
+    }
+
+    // ensure that the target is chronologically at or after the oldest observation
+    if !lte(time, before_or_at.block_timestamp, target) {
+        return Err(UniswapV3MathError::ObservationTooOld);
+    }
+
+    // if we've reached this point, we have to binary search
+    Ok(binary_search(
+        observations,
+        time,
+        target,
+        index,
+        cardinality,
+    ))
+}
+
+pub fn observe_single(
+    observations: &[Observation],
+    time: u32,
+    seconds_ago: u32,
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(i64, U256), UniswapV3MathError> {
+    if seconds_ago == 0 {
+        let mut last = observations[index as usize].clone();
+        if last.block_timestamp != time {
+            last = transform(&last, time, tick, liquidity);
+        }
+        return Ok((
+            last.tick_cumulative,
+            last.seconds_per_liquidity_cumulative_x128,
+        ));
+    }
+
+    let target = time.wrapping_sub(seconds_ago);
+
+    let (before_or_at, at_or_after) = get_surrounding_observations(
+        observations,
+        time,
+        target,
+        tick,
+        index,
+        liquidity,
+        cardinality,
+    )?;
+
+    if target == before_or_at.block_timestamp {
+        // we're at the left boundary
+        Ok((
+            before_or_at.tick_cumulative,
+            before_or_at.seconds_per_liquidity_cumulative_x128,
+        ))
+    } else if target == at_or_after.block_timestamp {
+        // we're at the right boundary
+        Ok((
+            at_or_after.tick_cumulative,
+            at_or_after.seconds_per_liquidity_cumulative_x128,
+        ))
+    } else {
+        // we're in the middle
+        let observation_time_delta = at_or_after
+            .block_timestamp
+            .wrapping_sub(before_or_at.block_timestamp) as i64;
+        let target_delta = target.wrapping_sub(before_or_at.block_timestamp) as i64;
+
+        Ok((
+            wrap_i56(
+                before_or_at.tick_cumulative
+                    + ((at_or_after.tick_cumulative - before_or_at.tick_cumulative)
+                        / observation_time_delta)
+                        * target_delta,
+            ),
+            before_or_at
+                .seconds_per_liquidity_cumulative_x128
+                .wrapping_add(
+                    (at_or_after
+                        .seconds_per_liquidity_cumulative_x128
+                        .wrapping_sub(before_or_at.seconds_per_liquidity_cumulative_x128)
+                        & MAX_U160)
+                        * U256::from(target_delta)
+                        / U256::from(observation_time_delta),
+                )
+                & MAX_U160,
+        ))
+    }
+}
+
+pub fn observe(
+    observations: &[Observation],
+    time: u32,
+    seconds_agos: &[u32],
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(Vec<i64>, Vec<U256>), UniswapV3MathError> {
+    if cardinality == 0 {
+        return Err(UniswapV3MathError::OracleNotInitialized);
+    }
+
+    let mut tick_cumulatives = Vec::with_capacity(seconds_agos.len());
+    let mut seconds_per_liquidity_cumulative_x128s = Vec::with_capacity(seconds_agos.len());
+    for &seconds_ago in seconds_agos {
+        let (tick_cumulative, seconds_per_liquidity_cumulative_x128) = observe_single(
+            observations,
+            time,
+            seconds_ago,
+            tick,
+            index,
+            liquidity,
+            cardinality,
+        )?;
+        tick_cumulatives.push(tick_cumulative);
+        seconds_per_liquidity_cumulative_x128s.push(seconds_per_liquidity_cumulative_x128);
+    }
+
+    Ok((tick_cumulatives, seconds_per_liquidity_cumulative_x128s))
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_primitives::U256;
+
+    use crate::libraries::full_math::Q128;
+    use crate::pool_data::Observation;
+
+    use super::{grow, initialize, lte, observe, observe_single, write};
+
+    fn observation(
+        block_timestamp: u32,
+        tick_cumulative: i64,
+        seconds_per_liquidity_cumulative_x128: U256,
+    ) -> Observation {
+        Observation {
+            block_timestamp,
+            tick_cumulative,
+            seconds_per_liquidity_cumulative_x128,
+            initialized: true,
+        }
+    }
+
+    #[test]
+    fn test_initialize() {
+        //index is 0, cardinality and cardinality next are 1
+        let mut observations = Vec::new();
+        assert_eq!(initialize(&mut observations, 5), (1, 1));
+        assert_eq!(observations, vec![observation(5, 0, U256::ZERO)]);
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut observations = Vec::new();
+        let (cardinality, _) = initialize(&mut observations, 5);
+
+        //increases the cardinality next and fills the new slots
+        assert_eq!(grow(&mut observations, cardinality, 5).unwrap(), 5);
+        assert_eq!(observations.len(), 5);
+        assert!(
+            observations[1..]
+                .iter()
+                .all(|observation| observation.block_timestamp == 1 && !observation.initialized)
+        );
+
+        //is a no op if the target is not greater than the current cardinality
+        assert_eq!(grow(&mut observations, 5, 3).unwrap(), 5);
+        assert_eq!(observations.len(), 5);
+
+        //fails before initialize
+        let result = grow(&mut Vec::new(), 0, 2);
+        assert_eq!(result.unwrap_err().to_string(), "Oracle is not initialized");
+    }
+
+    #[test]
+    fn test_write() {
+        //single element array gets overwritten
+        let mut observations = Vec::new();
+        let (cardinality, cardinality_next) = initialize(&mut observations, 0);
+        let (index, cardinality) =
+            write(&mut observations, 0, 1, 1, 5, cardinality, cardinality_next);
+        assert_eq!((index, cardinality), (0, 1));
+        assert_eq!(observations[0], observation(1, 1, Q128 / U256::from(5)));
+
+        let (index, cardinality) = write(
+            &mut observations,
+            0,
+            6,
+            -1,
+            8,
+            cardinality,
+            cardinality_next,
+        );
+        assert_eq!((index, cardinality), (0, 1));
+        assert_eq!(
+            observations[0],
+            observation(
+                6,
+                -4,
+                Q128 / U256::from(5) + Q128 * U256::from(5) / U256::from(8)
+            )
+        );
+
+        //does nothing if time has not changed
+        let (index, cardinality) =
+            write(&mut observations, 0, 6, 3, 1, cardinality, cardinality_next);
+        assert_eq!((index, cardinality), (0, 1));
+        assert_eq!(observations[0].tick_cumulative, -4);
+
+        //treats zero liquidity as one
+        let (index, _) = write(&mut observations, 0, 7, 0, 0, cardinality, cardinality_next);
+        assert_eq!(
+            observations[index as usize].seconds_per_liquidity_cumulative_x128,
+            Q128 / U256::from(5) + Q128 * U256::from(5) / U256::from(8) + Q128
+        );
+
+        //grows the cardinality once the last slot is written, then wraps around
+        let mut observations = Vec::new();
+        let (cardinality, _) = initialize(&mut observations, 0);
+        let cardinality_next = grow(&mut observations, cardinality, 3).unwrap();
+        let (index, cardinality) =
+            write(&mut observations, 0, 1, 2, 1, cardinality, cardinality_next);
+        assert_eq!((index, cardinality), (1, 3));
+        let (index, cardinality) = write(
+            &mut observations,
+            index,
+            2,
+            2,
+            1,
+            cardinality,
+            cardinality_next,
+        );
+        assert_eq!((index, cardinality), (2, 3));
+        let (index, cardinality) = write(
+            &mut observations,
+            index,
+            3,
+            2,
+            1,
+            cardinality,
+            cardinality_next,
+        );
+        assert_eq!((index, cardinality), (0, 3));
+        assert_eq!(observations[0], observation(3, 6, Q128 * U256::from(3)));
+    }
+
+    #[test]
+    fn test_lte() {
+        //without overflow
+        assert!(lte(10, 3, 5));
+        assert!(lte(10, 5, 5));
+        assert!(!lte(10, 6, 5));
+
+        //timestamps after time are from before the overflow
+        assert!(lte(5, u32::MAX - 10, 3));
+        assert!(!lte(5, 3, u32::MAX - 10));
+        assert!(lte(5, u32::MAX - 10, u32::MAX - 5));
+    }
+
+    #[test]
+    fn test_observe() {
+        //fails before initialize
+        let result = observe(&[], 0, &[0], 0, 0, 0, 0);
+        assert_eq!(result.unwrap_err().to_string(), "Oracle is not initialized");
+
+        let mut observations = Vec::new();
+        let (cardinality, _) = initialize(&mut observations, 5);
+        let cardinality_next = grow(&mut observations, cardinality, 4).unwrap();
+
+        //single observation at the current time
+        assert_eq!(
+            observe_single(&observations, 5, 0, 3, 0, 4, cardinality).unwrap(),
+            (0, U256::ZERO)
+        );
+
+        //single observation, transformed to the current time
+        assert_eq!(
+            observe_single(&observations, 8, 0, 2, 0, 4, cardinality).unwrap(),
+            (6, Q128 * U256::from(3) / U256::from(4))
+        );
+
+        let (index, cardinality) = write(
+            &mut observations,
+            0,
+            10,
+            5,
+            1,
+            cardinality,
+            cardinality_next,
+        );
+        let (index, cardinality) = write(
+            &mut observations,
+            index,
+            20,
+            -3,
+            2,
+            cardinality,
+            cardinality_next,
+        );
+
+        let (tick_cumulatives, seconds_per_liquidity_cumulative_x128s) = observe(
+            &observations,
+            30,
+            &[0, 5, 10, 15, 20, 25],
+            7,
+            index,
+            4,
+            cardinality,
+        )
+        .unwrap();
+        assert_eq!(tick_cumulatives, vec![65, 30, -5, 10, 25, 0]);
+        assert_eq!(
+            seconds_per_liquidity_cumulative_x128s,
+            [
+                "4253529586511730793292182592897102643200",
+                "3828176627860557713962964333607392378880",
+                "3402823669209384634633746074317682114560",
+                "2552117751907038475975309555738261585920",
+                "1701411834604692317316873037158841057280",
+                "0",
+            ]
+            .map(|value| value.parse::<U256>().unwrap())
+        );
+
+        //fails if the target is older than the oldest observation
+        let result = observe(&observations, 30, &[26], 7, index, 4, cardinality);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Target is older than the oldest observation"
+        );
+
+        //works across the timestamp overflow
+        let mut observations = Vec::new();
+        let (cardinality, _) = initialize(&mut observations, u32::MAX - 4);
+        let cardinality_next = grow(&mut observations, cardinality, 2).unwrap();
+        let (index, cardinality) =
+            write(&mut observations, 0, 5, 2, 1, cardinality, cardinality_next);
+        assert_eq!(observations[index as usize].tick_cumulative, 20);
+        assert_eq!(
+            observe(&observations, 15, &[0, 10, 12], -1, index, 1, cardinality)
+                .unwrap()
+                .0,
+            vec![10, 20, 16]
+        );
+    }
+}
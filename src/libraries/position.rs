@@ -0,0 +1,103 @@
+use alloy_primitives::U256;
+
+use crate::libraries::{error::UniswapV3MathError, full_math, liquidity_math};
+use crate::pool_data::PositionInfo;
+
+pub fn update(
+    position: &mut PositionInfo,
+    liquidity_delta: i128,
+    fee_growth_inside0_x128: U256,
+    fee_growth_inside1_x128: U256,
+) -> Result<(), UniswapV3MathError> {
+    let liquidity_next = if liquidity_delta == 0 {
+        // disallow pokes for 0 liquidity positions
+        if position.liquidity == 0 {
+            return Err(UniswapV3MathError::NoLiquidityPosition);
+        }
+        position.liquidity
+    } else {
+        liquidity_math::add_delta(position.liquidity, liquidity_delta)?
+    };
+
+    // calculate accumulated fees
+    let tokens_owed0 = full_math::mul_div(
+        fee_growth_inside0_x128.wrapping_sub(position.fee_growth_inside0_last_x128),
+        U256::from(position.liquidity),
+        full_math::Q128,
+    )?
+    .wrapping_to::<u128>();
+    let tokens_owed1 = full_math::mul_div(
+        fee_growth_inside1_x128.wrapping_sub(position.fee_growth_inside1_last_x128),
+        U256::from(position.liquidity),
+        full_math::Q128,
+    )?
+    .wrapping_to::<u128>();
+
+    // update the position
+    position.liquidity = liquidity_next;
+    position.fee_growth_inside0_last_x128 = fee_growth_inside0_x128;
+    position.fee_growth_inside1_last_x128 = fee_growth_inside1_x128;
+    // overflow is acceptable, have to withdraw before you hit type(uint128).max fees
+    position.tokens_owed0 = position.tokens_owed0.wrapping_add(tokens_owed0);
+    position.tokens_owed1 = position.tokens_owed1.wrapping_add(tokens_owed1);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_primitives::U256;
+
+    use crate::libraries::full_math::Q128;
+    use crate::pool_data::PositionInfo;
+
+    use super::update;
+
+    #[test]
+    fn test_update() {
+        //disallows pokes for 0 liquidity positions
+        let mut position = PositionInfo::default();
+        let result = update(&mut position, 0, U256::ZERO, U256::ZERO);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cannot poke a position without liquidity"
+        );
+        assert_eq!(position, PositionInfo::default());
+
+        //does not accrue fees on liquidity that was just added
+        update(&mut position, 100, Q128, Q128 * U256::from(2)).unwrap();
+        assert_eq!(position.liquidity, 100);
+        assert_eq!(position.tokens_owed0, 0);
+        assert_eq!(position.fee_growth_inside0_last_x128, Q128);
+
+        //accrues fees on the existing liquidity
+        update(
+            &mut position,
+            50,
+            Q128 * U256::from(3),
+            Q128 * U256::from(3),
+        )
+        .unwrap();
+        assert_eq!(position.liquidity, 150);
+        assert_eq!((position.tokens_owed0, position.tokens_owed1), (200, 100));
+
+        //pokes accrue fees without changing liquidity
+        update(&mut position, 0, Q128 * U256::from(4), Q128 * U256::from(3)).unwrap();
+        assert_eq!(position.liquidity, 150);
+        assert_eq!((position.tokens_owed0, position.tokens_owed1), (350, 100));
+
+        //fee growth inside may wrap around
+        let mut position = PositionInfo {
+            liquidity: 1,
+            fee_growth_inside0_last_x128: U256::MAX,
+            ..Default::default()
+        };
+        update(&mut position, 0, Q128 - U256::from(1), U256::ZERO).unwrap();
+        assert_eq!(position.tokens_owed0, 1);
+
+        //cannot burn more than the position holds
+        let result = update(&mut position, -2, U256::ZERO, U256::ZERO);
+        assert_eq!(result.unwrap_err().to_string(), "Liquidity Sub");
+        assert_eq!(position.liquidity, 1);
+    }
+}
@@ -1,4 +1,4 @@
-use alloy_primitives::U256;
+use alloy_primitives::{I256, U256};
 
 use crate::libraries::{
     error::UniswapV3MathError,
@@ -176,6 +176,51 @@ pub fn _get_amount_1_delta(
         )
     }
 }
+
+pub fn get_amount_0_delta(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: i128,
+) -> Result<I256, UniswapV3MathError> {
+    if liquidity < 0 {
+        Ok(-I256::from_raw(_get_amount_0_delta(
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity.unsigned_abs(),
+            false,
+        )?))
+    } else {
+        Ok(I256::from_raw(_get_amount_0_delta(
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity as u128,
+            true,
+        )?))
+    }
+}
+
+pub fn get_amount_1_delta(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: i128,
+) -> Result<I256, UniswapV3MathError> {
+    if liquidity < 0 {
+        Ok(-I256::from_raw(_get_amount_1_delta(
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity.unsigned_abs(),
+            false,
+        )?))
+    } else {
+        Ok(I256::from_raw(_get_amount_1_delta(
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity as u128,
+            true,
+        )?))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::{
@@ -183,14 +228,16 @@ mod test {
         str::FromStr,
     };
 
-    use alloy_primitives::U256;
+    use alloy_primitives::{I256, U256};
 
     use crate::libraries::{
         U256_1, U256_2,
         sqrt_price_math::{_get_amount_1_delta, MAX_U160, get_next_sqrt_price_from_output},
     };
 
-    use super::{_get_amount_0_delta, get_next_sqrt_price_from_input};
+    use super::{
+        _get_amount_0_delta, get_amount_0_delta, get_amount_1_delta, get_next_sqrt_price_from_input,
+    };
 
     #[test]
     fn test_get_next_sqrt_price_from_input() {
@@ -579,6 +626,38 @@ mod test {
         assert_eq!(amount_1_rounded_down.unwrap(), amount_1.sub(U256_1));
     }
 
+    #[test]
+    fn test_get_amount_delta_signed() {
+        let sqrt_price_a = U256::from_str("79228162514264337593543950336").unwrap();
+        let sqrt_price_b = U256::from_str("87150978765690771352898345369").unwrap();
+
+        // rounds up when liquidity is added
+        assert_eq!(
+            get_amount_0_delta(sqrt_price_a, sqrt_price_b, 1e18 as i128).unwrap(),
+            I256::from_str("90909090909090910").unwrap()
+        );
+        assert_eq!(
+            get_amount_1_delta(sqrt_price_a, sqrt_price_b, 1e18 as i128).unwrap(),
+            I256::from_str("100000000000000000").unwrap()
+        );
+
+        // rounds down and negates when liquidity is removed
+        assert_eq!(
+            get_amount_0_delta(sqrt_price_a, sqrt_price_b, -1e18 as i128).unwrap(),
+            I256::from_str("-90909090909090909").unwrap()
+        );
+        assert_eq!(
+            get_amount_1_delta(sqrt_price_b, sqrt_price_a, -1e18 as i128).unwrap(),
+            I256::from_str("-99999999999999999").unwrap()
+        );
+
+        // returns 0 if liquidity is 0
+        assert_eq!(
+            get_amount_0_delta(sqrt_price_a, sqrt_price_b, 0).unwrap(),
+            I256::ZERO
+        );
+    }
+
     #[test]
     fn test_swap_computation() {
         let sqrt_price =
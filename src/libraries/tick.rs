@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use alloy_primitives::U256;
+
+use crate::libraries::{error::UniswapV3MathError, liquidity_math, tick_math};
+use crate::pool_data::TickInfo;
+
+pub fn tick_spacing_to_max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = (tick_math::MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (tick_math::MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update(
+    ticks: &mut HashMap<i32, TickInfo>,
+    tick: i32,
+    tick_current: i32,
+    liquidity_delta: i128,
+    fee_growth_global0_x128: U256,
+    fee_growth_global1_x128: U256,
+    upper: bool,
+    max_liquidity: u128,
+) -> Result<bool, UniswapV3MathError> {
+    let info = ticks.get(&tick).cloned().unwrap_or_default();
+
+    let liquidity_gross_before = info.liquidity_gross;
+    let liquidity_gross_after = liquidity_math::add_delta(liquidity_gross_before, liquidity_delta)?;
+    if liquidity_gross_after > max_liquidity {
+        return Err(UniswapV3MathError::LiquidityGrossOverflow);
+    }
+
+    // when the lower (upper) tick is crossed left to right (right to left), liquidity must be added (removed)
+    let liquidity_net = if upper {
+        info.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        info.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?;
+
+    let info = ticks.entry(tick).or_default();
+    if liquidity_gross_before == 0 {
+        // by convention, we assume that all growth before a tick was initialized happened _below_ the tick
+        if tick <= tick_current {
+            info.fee_growth_outside0_x128 = fee_growth_global0_x128;
+            info.fee_growth_outside1_x128 = fee_growth_global1_x128;
+        }
+    }
+    info.liquidity_gross = liquidity_gross_after;
+    info.liquidity_net = liquidity_net;
+
+    Ok((liquidity_gross_after == 0) != (liquidity_gross_before == 0))
+}
+
+pub fn get_fee_growth_inside(
+    ticks: &HashMap<i32, TickInfo>,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global0_x128: U256,
+    fee_growth_global1_x128: U256,
+) -> (U256, U256) {
+    let lower = ticks.get(&tick_lower).cloned().unwrap_or_default();
+    let upper = ticks.get(&tick_upper).cloned().unwrap_or_default();
+
+    // calculate fee growth below
+    let (fee_growth_below0_x128, fee_growth_below1_x128) = if tick_current >= tick_lower {
+        (
+            lower.fee_growth_outside0_x128,
+            lower.fee_growth_outside1_x128,
+        )
+    } else {
+        (
+            fee_growth_global0_x128.wrapping_sub(lower.fee_growth_outside0_x128),
+            fee_growth_global1_x128.wrapping_sub(lower.fee_growth_outside1_x128),
+        )
+    };
+
+    // calculate fee growth above
+    let (fee_growth_above0_x128, fee_growth_above1_x128) = if tick_current < tick_upper {
+        (
+            upper.fee_growth_outside0_x128,
+            upper.fee_growth_outside1_x128,
+        )
+    } else {
+        (
+            fee_growth_global0_x128.wrapping_sub(upper.fee_growth_outside0_x128),
+            fee_growth_global1_x128.wrapping_sub(upper.fee_growth_outside1_x128),
+        )
+    };
+
+    (
+        fee_growth_global0_x128
+            .wrapping_sub(fee_growth_below0_x128)
+            .wrapping_sub(fee_growth_above0_x128),
+        fee_growth_global1_x128
+            .wrapping_sub(fee_growth_below1_x128)
+            .wrapping_sub(fee_growth_above1_x128),
+    )
+}
+
+pub fn clear(ticks: &mut HashMap<i32, TickInfo>, tick: i32) {
+    ticks.remove(&tick);
+}
+
+pub fn cross(
+    ticks: &mut HashMap<i32, TickInfo>,
+    tick: i32,
+    fee_growth_global0_x128: U256,
+    fee_growth_global1_x128: U256,
+) -> i128 {
+    let info = ticks.entry(tick).or_default();
+    info.fee_growth_outside0_x128 =
+        fee_growth_global0_x128.wrapping_sub(info.fee_growth_outside0_x128);
+    info.fee_growth_outside1_x128 =
+        fee_growth_global1_x128.wrapping_sub(info.fee_growth_outside1_x128);
+
+    info.liquidity_net
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use alloy_primitives::U256;
+
+    use crate::pool_data::TickInfo;
+
+    use super::{
+        clear, cross, get_fee_growth_inside, tick_spacing_to_max_liquidity_per_tick, update,
+    };
+
+    fn tick_info(fee_growth_outside0_x128: U256, fee_growth_outside1_x128: U256) -> TickInfo {
+        TickInfo {
+            liquidity_gross: 0,
+            liquidity_net: 0,
+            fee_growth_outside0_x128,
+            fee_growth_outside1_x128,
+        }
+    }
+
+    fn update_tick(
+        ticks: &mut HashMap<i32, TickInfo>,
+        tick: i32,
+        tick_current: i32,
+        liquidity_delta: i128,
+        upper: bool,
+        max_liquidity: u128,
+    ) -> bool {
+        update(
+            ticks,
+            tick,
+            tick_current,
+            liquidity_delta,
+            U256::ZERO,
+            U256::ZERO,
+            upper,
+            max_liquidity,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tick_spacing_to_max_liquidity_per_tick() {
+        //returns the correct value for low fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(10),
+            1917569901783203986719870431555990
+        );
+
+        //returns the correct value for medium fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(60),
+            11505743598341114571880798222544994
+        );
+
+        //returns the correct value for high fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(200),
+            38350317471085141830651933667504588
+        );
+
+        //returns the correct value for the entire range
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(887272),
+            u128::MAX / 3
+        );
+
+        //returns the correct value for 2302
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(2302),
+            441351967472034323558203122479595605
+        );
+    }
+
+    #[test]
+    fn test_get_fee_growth_inside() {
+        let global = (U256::from(15), U256::from(15));
+
+        //returns all for two uninitialized ticks if tick is inside
+        let mut ticks = HashMap::new();
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, global.0, global.1),
+            (U256::from(15), U256::from(15))
+        );
+
+        //returns 0 for two uninitialized ticks if tick is above
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 4, global.0, global.1),
+            (U256::ZERO, U256::ZERO)
+        );
+
+        //returns 0 for two uninitialized ticks if tick is below
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, -4, global.0, global.1),
+            (U256::ZERO, U256::ZERO)
+        );
+
+        //subtracts upper tick if below
+        ticks.insert(2, tick_info(U256::from(2), U256::from(3)));
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, global.0, global.1),
+            (U256::from(13), U256::from(12))
+        );
+
+        //subtracts lower tick if above
+        let mut ticks = HashMap::new();
+        ticks.insert(-2, tick_info(U256::from(2), U256::from(3)));
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, global.0, global.1),
+            (U256::from(13), U256::from(12))
+        );
+
+        //subtracts upper and lower tick if inside
+        ticks.insert(2, tick_info(U256::from(4), U256::from(1)));
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, global.0, global.1),
+            (U256::from(9), U256::from(11))
+        );
+
+        //works correctly with overflow on inside tick
+        ticks.insert(
+            -2,
+            tick_info(U256::MAX - U256::from(3), U256::MAX - U256::from(2)),
+        );
+        ticks.insert(2, tick_info(U256::from(3), U256::from(5)));
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, global.0, global.1),
+            (U256::from(16), U256::from(13))
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        //flips from zero to nonzero
+        let mut ticks = HashMap::new();
+        assert!(update_tick(&mut ticks, 0, 0, 1, false, 3));
+
+        //does not flip from nonzero to greater nonzero
+        assert!(!update_tick(&mut ticks, 0, 0, 1, false, 3));
+
+        //flips from nonzero to zero
+        let mut ticks = HashMap::new();
+        update_tick(&mut ticks, 0, 0, 1, false, 3);
+        assert!(update_tick(&mut ticks, 0, 0, -1, false, 3));
+
+        //does not flip from nonzero to lesser nonzero
+        let mut ticks = HashMap::new();
+        update_tick(&mut ticks, 0, 0, 2, false, 3);
+        assert!(!update_tick(&mut ticks, 0, 0, -1, false, 3));
+
+        //reverts if total liquidity gross is greater than max
+        let mut ticks = HashMap::new();
+        update_tick(&mut ticks, 0, 0, 2, false, 3);
+        update_tick(&mut ticks, 0, 0, 1, true, 3);
+        let result = update(&mut ticks, 0, 0, 1, U256::ZERO, U256::ZERO, false, 3);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Liquidity gross exceeds the max liquidity per tick"
+        );
+        assert_eq!(ticks[&0].liquidity_gross, 3);
+
+        //nets the liquidity based on upper flag
+        let mut ticks = HashMap::new();
+        update_tick(&mut ticks, 0, 0, 2, false, 10);
+        update_tick(&mut ticks, 0, 0, 1, true, 10);
+        update_tick(&mut ticks, 0, 0, 3, true, 10);
+        update_tick(&mut ticks, 0, 0, 1, false, 10);
+        assert_eq!(ticks[&0].liquidity_gross, 2 + 1 + 3 + 1);
+        assert_eq!(ticks[&0].liquidity_net, 2 - 1 - 3 + 1);
+
+        //reverts on overflow liquidity net
+        let mut ticks = HashMap::new();
+        update_tick(&mut ticks, 0, 0, i128::MAX / 2 - 1, false, u128::MAX);
+        update_tick(&mut ticks, 0, 0, i128::MAX / 2 - 1, false, u128::MAX);
+        let result = update(
+            &mut ticks,
+            0,
+            0,
+            i128::MAX / 2 - 1,
+            U256::ZERO,
+            U256::ZERO,
+            false,
+            u128::MAX,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to i128"
+        );
+
+        //assumes all growth happens below ticks lte current tick
+        let mut ticks = HashMap::new();
+        update(
+            &mut ticks,
+            1,
+            1,
+            1,
+            U256::from(1),
+            U256::from(2),
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        assert_eq!(ticks[&1].fee_growth_outside0_x128, U256::from(1));
+        assert_eq!(ticks[&1].fee_growth_outside1_x128, U256::from(2));
+
+        //does not set any growth fields if tick is already initialized
+        update(
+            &mut ticks,
+            1,
+            1,
+            1,
+            U256::from(6),
+            U256::from(7),
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        assert_eq!(ticks[&1].fee_growth_outside0_x128, U256::from(1));
+        assert_eq!(ticks[&1].fee_growth_outside1_x128, U256::from(2));
+
+        //does not set any growth fields for ticks gt current tick
+        let mut ticks = HashMap::new();
+        update(
+            &mut ticks,
+            2,
+            1,
+            1,
+            U256::from(1),
+            U256::from(2),
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        assert_eq!(ticks[&2].fee_growth_outside0_x128, U256::ZERO);
+        assert_eq!(ticks[&2].fee_growth_outside1_x128, U256::ZERO);
+    }
+
+    #[test]
+    fn test_clear() {
+        //deletes all the data in the tick
+        let mut ticks = HashMap::new();
+        update(
+            &mut ticks,
+            2,
+            1,
+            3,
+            U256::from(1),
+            U256::from(2),
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        clear(&mut ticks, 2);
+        assert!(!ticks.contains_key(&2));
+    }
+
+    #[test]
+    fn test_cross() {
+        //flips the growth variables
+        let mut ticks = HashMap::new();
+        ticks.insert(
+            2,
+            TickInfo {
+                liquidity_gross: 3,
+                liquidity_net: 4,
+                fee_growth_outside0_x128: U256::from(1),
+                fee_growth_outside1_x128: U256::from(2),
+            },
+        );
+        let liquidity_net = cross(&mut ticks, 2, U256::from(7), U256::from(9));
+        assert_eq!(liquidity_net, 4);
+        assert_eq!(ticks[&2].fee_growth_outside0_x128, U256::from(6));
+        assert_eq!(ticks[&2].fee_growth_outside1_x128, U256::from(7));
+        assert_eq!(ticks[&2].liquidity_gross, 3);
+
+        //two flips are no op
+        cross(&mut ticks, 2, U256::from(7), U256::from(9));
+        assert_eq!(ticks[&2].fee_growth_outside0_x128, U256::from(1));
+        assert_eq!(ticks[&2].fee_growth_outside1_x128, U256::from(2));
+
+        //wraps around when the outside value exceeds the global value
+        let liquidity_net = cross(&mut ticks, 2, U256::ZERO, U256::ZERO);
+        assert_eq!(liquidity_net, 4);
+        assert_eq!(ticks[&2].fee_growth_outside0_x128, U256::MAX);
+        assert_eq!(
+            ticks[&2].fee_growth_outside1_x128,
+            U256::MAX - U256::from(1)
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{I16, U256};
+
+use crate::libraries::{U256_1, bit_math, error::UniswapV3MathError};
+
+pub fn position(tick: i32) -> (I16, u8) {
+    (I16::unchecked_from(tick >> 8), (tick & 0xFF) as u8)
+}
+
+pub fn flip_tick(
+    tick_bitmap: &mut HashMap<I16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+) -> Result<(), UniswapV3MathError> {
+    if tick % tick_spacing != 0 {
+        return Err(UniswapV3MathError::TickSpacingError);
+    }
+
+    let (word_pos, bit_pos) = position(tick / tick_spacing);
+    let mask = U256_1 << bit_pos;
+    let word = tick_bitmap.entry(word_pos).or_insert(U256::ZERO);
+    *word ^= mask;
+
+    Ok(())
+}
+
+pub fn next_initialized_tick_within_one_word(
+    tick_bitmap: &HashMap<I16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> Result<(i32, bool), UniswapV3MathError> {
+    let compressed = if tick < 0 && tick % tick_spacing != 0 {
+        (tick / tick_spacing) - 1
+    } else {
+        tick / tick_spacing
+    };
+
+    if lte {
+        let (word_pos, bit_pos) = position(compressed);
+        // all the 1s at or to the right of the current bit_pos
+        let mask = (U256_1 << bit_pos) - U256_1 + (U256_1 << bit_pos);
+        let masked = tick_bitmap.get(&word_pos).copied().unwrap_or_default() & mask;
+
+        let initialized = !masked.is_zero();
+        let next = if initialized {
+            (compressed - (bit_pos - bit_math::most_significant_bit(masked)?) as i32) * tick_spacing
+        } else {
+            (compressed - bit_pos as i32) * tick_spacing
+        };
+
+        Ok((next, initialized))
+    } else {
+        // start from the word of the next tick, since the current tick state doesn't matter
+        let (word_pos, bit_pos) = position(compressed + 1);
+        // all the 1s at or to the left of the bit_pos
+        let mask = !((U256_1 << bit_pos) - U256_1);
+        let masked = tick_bitmap.get(&word_pos).copied().unwrap_or_default() & mask;
+
+        let initialized = !masked.is_zero();
+        let next = if initialized {
+            (compressed + 1 + (bit_math::least_significant_bit(masked)? - bit_pos) as i32)
+                * tick_spacing
+        } else {
+            (compressed + 1 + (u8::MAX - bit_pos) as i32) * tick_spacing
+        };
+
+        Ok((next, initialized))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use alloy_primitives::{I16, U256};
+
+    use crate::libraries::U256_1;
+
+    use super::{flip_tick, next_initialized_tick_within_one_word, position};
+
+    fn is_initialized(tick_bitmap: &HashMap<I16, U256>, tick: i32) -> bool {
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(tick_bitmap, tick, 1, true).unwrap();
+        next == tick && initialized
+    }
+
+    fn init_ticks(ticks: &[i32]) -> HashMap<I16, U256> {
+        let mut tick_bitmap = HashMap::new();
+        for &tick in ticks {
+            flip_tick(&mut tick_bitmap, tick, 1).unwrap();
+        }
+        tick_bitmap
+    }
+
+    #[test]
+    fn test_position() {
+        assert_eq!(position(0), (I16::ZERO, 0));
+        assert_eq!(position(255), (I16::ZERO, 255));
+        assert_eq!(position(256), (I16::ONE, 0));
+        assert_eq!(position(-1), (I16::MINUS_ONE, 255));
+        assert_eq!(position(-256), (I16::MINUS_ONE, 0));
+        assert_eq!(position(-257), (I16::unchecked_from(-2), 255));
+        assert_eq!(position(-887272), (I16::unchecked_from(-3466), 24));
+        assert_eq!(position(887272), (I16::unchecked_from(3465), 232));
+    }
+
+    #[test]
+    fn test_is_initialized() {
+        //is false at first
+        let mut tick_bitmap = HashMap::new();
+        assert!(!is_initialized(&tick_bitmap, 1));
+
+        //is flipped by #flipTick
+        flip_tick(&mut tick_bitmap, 1, 1).unwrap();
+        assert!(is_initialized(&tick_bitmap, 1));
+
+        //is flipped back by #flipTick
+        flip_tick(&mut tick_bitmap, 1, 1).unwrap();
+        assert!(!is_initialized(&tick_bitmap, 1));
+
+        //is not changed by another flip to a different tick
+        flip_tick(&mut tick_bitmap, 2, 1).unwrap();
+        assert!(!is_initialized(&tick_bitmap, 1));
+
+        //is not changed by another flip to a different tick on another word
+        flip_tick(&mut tick_bitmap, 1 + 256, 1).unwrap();
+        assert!(is_initialized(&tick_bitmap, 257));
+        assert!(!is_initialized(&tick_bitmap, 1));
+    }
+
+    #[test]
+    fn test_flip_tick() {
+        //flips only the specified tick
+        let mut tick_bitmap = HashMap::new();
+        flip_tick(&mut tick_bitmap, -230, 1).unwrap();
+        assert!(is_initialized(&tick_bitmap, -230));
+        assert!(!is_initialized(&tick_bitmap, -231));
+        assert!(!is_initialized(&tick_bitmap, -229));
+        assert!(!is_initialized(&tick_bitmap, -230 + 256));
+        assert!(!is_initialized(&tick_bitmap, -230 - 256));
+        flip_tick(&mut tick_bitmap, -230, 1).unwrap();
+        assert!(!is_initialized(&tick_bitmap, -230));
+
+        //reverts only itself
+        let mut tick_bitmap = init_ticks(&[-230, -259, -229, 500, -259, -229, -259]);
+        assert!(is_initialized(&tick_bitmap, -259));
+        assert!(!is_initialized(&tick_bitmap, -229));
+
+        //fails if the tick is not a multiple of the spacing
+        let result = flip_tick(&mut tick_bitmap, 61, 60);
+        assert_eq!(result.unwrap_err().to_string(), "Tick spacing error");
+
+        //compresses by the tick spacing
+        flip_tick(&mut tick_bitmap, -120, 60).unwrap();
+        assert_eq!(
+            tick_bitmap
+                .get(&I16::MINUS_ONE)
+                .copied()
+                .unwrap_or_default()
+                & (U256_1 << 254),
+            U256_1 << 254
+        );
+    }
+
+    #[test]
+    fn test_next_initialized_tick_within_one_word_lte_false() {
+        let mut tick_bitmap = init_ticks(&[-200, -55, -4, 70, 78, 84, 139, 240, 535]);
+
+        //returns tick to right if at initialized tick
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 78, 1, false).unwrap();
+        assert_eq!((next, initialized), (84, true));
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -55, 1, false).unwrap();
+        assert_eq!((next, initialized), (-4, true));
+
+        //returns the tick directly to the right
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 77, 1, false).unwrap();
+        assert_eq!((next, initialized), (78, true));
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -56, 1, false).unwrap();
+        assert_eq!((next, initialized), (-55, true));
+
+        //returns the next words initialized tick if on the right boundary
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 255, 1, false).unwrap();
+        assert_eq!((next, initialized), (511, false));
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -257, 1, false).unwrap();
+        assert_eq!((next, initialized), (-200, true));
+
+        //does not exceed boundary
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 508, 1, false).unwrap();
+        assert_eq!((next, initialized), (511, false));
+
+        //skips half word
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 383, 1, false).unwrap();
+        assert_eq!((next, initialized), (511, false));
+
+        //returns the next initialized tick from the next word
+        flip_tick(&mut tick_bitmap, 340, 1).unwrap();
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 328, 1, false).unwrap();
+        assert_eq!((next, initialized), (340, true));
+    }
+
+    #[test]
+    fn test_next_initialized_tick_within_one_word_lte_true() {
+        let mut tick_bitmap = init_ticks(&[-200, -55, -4, 70, 78, 84, 139, 240, 535]);
+
+        //returns same tick if initialized
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 78, 1, true).unwrap();
+        assert_eq!((next, initialized), (78, true));
+
+        //returns tick directly to the left of input tick if not initialized
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 79, 1, true).unwrap();
+        assert_eq!((next, initialized), (78, true));
+
+        //will not exceed the word boundary
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 258, 1, true).unwrap();
+        assert_eq!((next, initialized), (256, false));
+
+        //at the word boundary
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 256, 1, true).unwrap();
+        assert_eq!((next, initialized), (256, false));
+
+        //word boundary less 1 (next initialized tick in next word)
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 72, 1, true).unwrap();
+        assert_eq!((next, initialized), (70, true));
+
+        //word boundary
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -257, 1, true).unwrap();
+        assert_eq!((next, initialized), (-512, false));
+
+        //entire empty word
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 1023, 1, true).unwrap();
+        assert_eq!((next, initialized), (768, false));
+
+        //halfway through empty word
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 900, 1, true).unwrap();
+        assert_eq!((next, initialized), (768, false));
+
+        //boundary is initialized
+        flip_tick(&mut tick_bitmap, 329, 1).unwrap();
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 456, 1, true).unwrap();
+        assert_eq!((next, initialized), (329, true));
+    }
+
+    #[test]
+    fn test_next_initialized_tick_within_one_word_tick_spacing() {
+        let tick_bitmap = {
+            let mut tick_bitmap = HashMap::new();
+            for tick in [-600, -60, 120, 15360] {
+                flip_tick(&mut tick_bitmap, tick, 60).unwrap();
+            }
+            tick_bitmap
+        };
+
+        //rounds negative ticks that are not a multiple of the spacing towards negative infinity
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -1, 60, true).unwrap();
+        assert_eq!((next, initialized), (-60, true));
+
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -61, 60, true).unwrap();
+        assert_eq!((next, initialized), (-600, true));
+
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, -60, 60, false).unwrap();
+        assert_eq!((next, initialized), (120, true));
+
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 119, 60, false).unwrap();
+        assert_eq!((next, initialized), (120, true));
+
+        //a word covers 256 * tick_spacing ticks
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 120, 60, false).unwrap();
+        assert_eq!((next, initialized), (15300, false));
+
+        let (next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, 15300, 60, false).unwrap();
+        assert_eq!((next, initialized), (15360, true));
+    }
+}
@@ -0,0 +1,361 @@
+use alloy_primitives::{Address, B256, Bytes, I256, U256, b256};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::events::{EventError, PoolEvent};
+
+/// `Swap(address,address,int256,int256,uint160,uint128,int24)`
+pub const SWAP_TOPIC: B256 =
+    b256!("0xc42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67");
+/// `Swap(address,address,int256,int256,uint160,uint128,int24,uint128,uint128)`, emitted by
+/// PancakeSwap V3 pools.
+pub const PANCAKE_SWAP_TOPIC: B256 =
+    b256!("0x19b47279256b2a23a1665c810c8d55a1758940ee09377d4f8d26497a3577dc83");
+/// `Mint(address,address,int24,int24,uint128,uint256,uint256)`
+pub const MINT_TOPIC: B256 =
+    b256!("0x7a53080ba414158be7ec69b987b5fb7d07dee101fe85488f0853ae16239d0bde");
+/// `Burn(address,int24,int24,uint128,uint256,uint256)`
+pub const BURN_TOPIC: B256 =
+    b256!("0x0c396cd989a39f4459b5fa1aed6a9a8dcdbc45908acfd67e028cd568da98982c");
+/// `Collect(address,address,int24,int24,uint128,uint128)`
+pub const COLLECT_TOPIC: B256 =
+    b256!("0x70935338e69775456a85ddef226c395fb668b63fa0115f5f20610b388e6ca9c0");
+/// `Flash(address,address,uint256,uint256,uint256,uint256)`
+pub const FLASH_TOPIC: B256 =
+    b256!("0xbdbdb71d7860376ba52b25a5028beea23581364a40522f6bcfb86bb1f2dca633");
+/// `SetFeeProtocol(uint8,uint8,uint8,uint8)`
+pub const SET_FEE_PROTOCOL_TOPIC: B256 =
+    b256!("0x973d8d92bb299f4af6ce49b52a8adb85ae46b9f214c4c4fc06ac77401237b133");
+/// `SetFeeProtocol(uint32,uint32,uint32,uint32)`, emitted by PancakeSwap V3 pools.
+pub const PANCAKE_SET_FEE_PROTOCOL_TOPIC: B256 =
+    b256!("0xb3159fed3ddfba67bae294599eafe2d0ec98c08bb38e0e5fb87d33154b6e05aa");
+/// `CollectProtocol(address,address,uint128,uint128)`
+pub const COLLECT_PROTOCOL_TOPIC: B256 =
+    b256!("0x596b573906218d3411850b26a6b437d6c4522fdb43d2d2386263f86d50b8b151");
+/// `IncreaseObservationCardinalityNext(uint16,uint16)`
+pub const INCREASE_OBSERVATION_CARDINALITY_NEXT_TOPIC: B256 =
+    b256!("0xac49e518f90a358f652e4400164f05a5d8f7e35e7747279bc3a93dbf584e125a");
+
+#[derive(Error, Debug)]
+pub enum LogError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid log on line {line}: {source}")]
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("{event} log has {actual} topics, expected {expected}")]
+    Topics {
+        event: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("{event} log has {actual} bytes of data, expected {expected}")]
+    Data {
+        event: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("{event} log has an out of range {field}")]
+    OutOfRange {
+        event: &'static str,
+        field: &'static str,
+    },
+    #[error("failed to apply log: {0}")]
+    Event(#[from] EventError),
+}
+
+/// A log as returned by `eth_getLogs` or found in a receipt. Fields other than the address,
+/// topics, data, block number and log index are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "JsonLog")]
+pub struct RawLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    pub block_number: Option<u64>,
+    pub log_index: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonLog {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    block_number: Option<String>,
+    log_index: Option<String>,
+}
+
+impl TryFrom<JsonLog> for RawLog {
+    type Error = String;
+
+    fn try_from(log: JsonLog) -> Result<Self, Self::Error> {
+        let quantity = |value: Option<String>| {
+            value
+                .map(|value| {
+                    let digits = value.strip_prefix("0x").unwrap_or(&value);
+                    u64::from_str_radix(digits, 16)
+                        .map_err(|_| format!("invalid quantity: {value}"))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            address: Address::from_str(&log.address)
+                .map_err(|_| format!("invalid address: {}", log.address))?,
+            topics: log
+                .topics
+                .iter()
+                .map(|topic| B256::from_str(topic).map_err(|_| format!("invalid topic: {topic}")))
+                .collect::<Result<_, _>>()?,
+            data: Bytes::from_str(&log.data).map_err(|_| format!("invalid data: {}", log.data))?,
+            block_number: quantity(log.block_number)?,
+            log_index: quantity(log.log_index)?,
+        })
+    }
+}
+
+/// A line of a JSONL dump, holding either a single log or a receipt with its logs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonLine {
+    Receipt { logs: Vec<RawLog> },
+    Log(RawLog),
+}
+
+/// Reads the logs of a JSONL dump, one log or receipt per line. Empty lines are skipped.
+pub fn read_logs(reader: impl BufRead) -> Result<Vec<RawLog>, LogError> {
+    let mut logs = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line).map_err(|source| LogError::Json {
+            line: index + 1,
+            source,
+        })? {
+            JsonLine::Receipt { logs: receipt_logs } => logs.extend(receipt_logs),
+            JsonLine::Log(log) => logs.push(log),
+        }
+    }
+    Ok(logs)
+}
+
+pub fn read_logs_file(path: impl AsRef<Path>) -> Result<Vec<RawLog>, LogError> {
+    read_logs(BufReader::new(File::open(path)?))
+}
+
+impl RawLog {
+    pub fn decode(&self) -> Result<Option<PoolEvent>, LogError> {
+        decode_log(&self.topics, &self.data)
+    }
+}
+
+/// Decodes a log emitted by a pool. Returns `None` for logs that aren't pool events.
+pub fn decode_log(topics: &[B256], data: &[u8]) -> Result<Option<PoolEvent>, LogError> {
+    let Some(&topic0) = topics.first() else {
+        return Ok(None);
+    };
+
+    let event = match topic0 {
+        SWAP_TOPIC | PANCAKE_SWAP_TOPIC => {
+            let pancake = topic0 == PANCAKE_SWAP_TOPIC;
+            let log = Log::new("Swap", topics, data, 3, if pancake { 7 } else { 5 })?;
+            PoolEvent::Swap {
+                sender: log.topic_address(1),
+                recipient: log.topic_address(2),
+                amount0: log.int256(0),
+                amount1: log.int256(1),
+                sqrt_price_x96: log.word(2),
+                liquidity: log.uint(3, "liquidity")?,
+                tick: log.int24(log.int256(4), "tick")?,
+                protocol_fees: if pancake {
+                    Some((
+                        log.uint(5, "protocolFeesToken0")?,
+                        log.uint(6, "protocolFeesToken1")?,
+                    ))
+                } else {
+                    None
+                },
+            }
+        }
+        MINT_TOPIC => {
+            let log = Log::new("Mint", topics, data, 4, 4)?;
+            PoolEvent::Mint {
+                sender: Address::from_word(log.data_word(0)),
+                owner: log.topic_address(1),
+                tick_lower: log.int24(log.topic_int(2), "tickLower")?,
+                tick_upper: log.int24(log.topic_int(3), "tickUpper")?,
+                amount: log.uint(1, "amount")?,
+                amount0: log.word(2),
+                amount1: log.word(3),
+            }
+        }
+        BURN_TOPIC => {
+            let log = Log::new("Burn", topics, data, 4, 3)?;
+            PoolEvent::Burn {
+                owner: log.topic_address(1),
+                tick_lower: log.int24(log.topic_int(2), "tickLower")?,
+                tick_upper: log.int24(log.topic_int(3), "tickUpper")?,
+                amount: log.uint(0, "amount")?,
+                amount0: log.word(1),
+                amount1: log.word(2),
+            }
+        }
+        COLLECT_TOPIC => {
+            let log = Log::new("Collect", topics, data, 4, 3)?;
+            PoolEvent::Collect {
+                owner: log.topic_address(1),
+                recipient: Address::from_word(log.data_word(0)),
+                tick_lower: log.int24(log.topic_int(2), "tickLower")?,
+                tick_upper: log.int24(log.topic_int(3), "tickUpper")?,
+                amount0: log.uint(1, "amount0")?,
+                amount1: log.uint(2, "amount1")?,
+            }
+        }
+        FLASH_TOPIC => {
+            let log = Log::new("Flash", topics, data, 3, 4)?;
+            PoolEvent::Flash {
+                sender: log.topic_address(1),
+                recipient: log.topic_address(2),
+                amount0: log.word(0),
+                amount1: log.word(1),
+                paid0: log.word(2),
+                paid1: log.word(3),
+            }
+        }
+        SET_FEE_PROTOCOL_TOPIC | PANCAKE_SET_FEE_PROTOCOL_TOPIC => {
+            let log = Log::new("SetFeeProtocol", topics, data, 1, 4)?;
+            // uint8s on Uniswap, uint32s on PancakeSwap
+            let fee_protocol = |index, field| {
+                if topic0 == PANCAKE_SET_FEE_PROTOCOL_TOPIC {
+                    log.uint::<u32>(index, field)
+                } else {
+                    log.uint::<u8>(index, field).map(u32::from)
+                }
+            };
+            PoolEvent::SetFeeProtocol {
+                fee_protocol0_old: fee_protocol(0, "feeProtocol0Old")?,
+                fee_protocol1_old: fee_protocol(1, "feeProtocol1Old")?,
+                fee_protocol0_new: fee_protocol(2, "feeProtocol0New")?,
+                fee_protocol1_new: fee_protocol(3, "feeProtocol1New")?,
+            }
+        }
+        COLLECT_PROTOCOL_TOPIC => {
+            let log = Log::new("CollectProtocol", topics, data, 3, 2)?;
+            PoolEvent::CollectProtocol {
+                sender: log.topic_address(1),
+                recipient: log.topic_address(2),
+                amount0: log.uint(0, "amount0")?,
+                amount1: log.uint(1, "amount1")?,
+            }
+        }
+        INCREASE_OBSERVATION_CARDINALITY_NEXT_TOPIC => {
+            let log = Log::new("IncreaseObservationCardinalityNext", topics, data, 1, 2)?;
+            PoolEvent::IncreaseObservationCardinalityNext {
+                observation_cardinality_next_old: log.uint(0, "observationCardinalityNextOld")?,
+                observation_cardinality_next_new: log.uint(1, "observationCardinalityNextNew")?,
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(event))
+}
+
+/// A log of a known event, with the number of topics and data words checked.
+struct Log<'a> {
+    event: &'static str,
+    topics: &'a [B256],
+    data: &'a [u8],
+}
+
+impl<'a> Log<'a> {
+    fn new(
+        event: &'static str,
+        topics: &'a [B256],
+        data: &'a [u8],
+        topic_count: usize,
+        word_count: usize,
+    ) -> Result<Self, LogError> {
+        if topics.len() != topic_count {
+            return Err(LogError::Topics {
+                event,
+                expected: topic_count,
+                actual: topics.len(),
+            });
+        }
+        if data.len() != word_count * 32 {
+            return Err(LogError::Data {
+                event,
+                expected: word_count * 32,
+                actual: data.len(),
+            });
+        }
+        Ok(Self {
+            event,
+            topics,
+            data,
+        })
+    }
+
+    fn data_word(&self, index: usize) -> B256 {
+        B256::from_slice(&self.data[index * 32..(index + 1) * 32])
+    }
+
+    fn word(&self, index: usize) -> U256 {
+        U256::from_be_bytes(self.data_word(index).0)
+    }
+
+    fn int256(&self, index: usize) -> I256 {
+        I256::from_raw(self.word(index))
+    }
+
+    fn uint<T: TryFrom<U256>>(&self, index: usize, field: &'static str) -> Result<T, LogError> {
+        T::try_from(self.word(index)).map_err(|_| LogError::OutOfRange {
+            event: self.event,
+            field,
+        })
+    }
+
+    fn int24(&self, value: I256, field: &'static str) -> Result<i32, LogError> {
+        i32::try_from(value)
+            .ok()
+            .filter(|value| (-(1 << 23)..1 << 23).contains(value))
+            .ok_or(LogError::OutOfRange {
+                event: self.event,
+                field,
+            })
+    }
+
+    fn topic_address(&self, index: usize) -> Address {
+        Address::from_word(self.topics[index])
+    }
+
+    fn topic_int(&self, index: usize) -> I256 {
+        I256::from_raw(U256::from_be_bytes(self.topics[index].0))
+    }
+}
+
+impl UniswapV3Pool {
+    /// Decodes `log` and applies it with `apply_log` if it was emitted by this pool. Returns
+    /// whether the log was applied.
+    pub fn apply_raw_log(&mut self, log: &RawLog) -> Result<bool, LogError> {
+        if log.address != self.metadata.address {
+            return Ok(false);
+        }
+        let Some(event) = log.decode()? else {
+            return Ok(false);
+        };
+        self.apply_log(&event)?;
+        Ok(true)
+    }
+}
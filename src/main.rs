@@ -1,13 +1,27 @@
 use alloy_primitives::aliases::{I24, U24};
 use alloy_primitives::{Address, I16, I256, U256};
 use anyhow::Result;
-use serde_json;
 use std::collections::HashMap;
-use std::fs;
-use std::str::FromStr;
 
-mod libraries;
+pub mod arbitrage;
+mod callback;
+pub mod depth;
+pub mod events;
+#[cfg(feature = "alloy")]
+pub mod fetch;
+mod flash;
+pub mod ledger;
+pub mod libraries;
+pub mod logs;
+mod observations;
+pub mod path;
 mod pool_data;
+mod position;
+mod price_target;
+pub mod quoter;
+pub mod router;
+mod snapshot;
+pub mod snapshot_store;
 
 use libraries::*;
 use pool_data::*;
@@ -16,6 +30,7 @@ use crate::libraries::error::UniswapV3MathError;
 
 #[derive(Clone, Debug)]
 pub struct UniswapV3Pool {
+    pub metadata: PoolMetadata,
     pub token0: Address,
     pub token1: Address,
     pub fee: U24,
@@ -27,160 +42,112 @@ pub struct UniswapV3Pool {
     pub liquidity: u128,
     pub ticks: HashMap<i32, TickInfo>,
     pub tick_bitmap: HashMap<I16, U256>,
+    /// Positions keyed by owner, lower tick and upper tick.
+    pub positions: HashMap<(Address, i32, i32), PositionInfo>,
+    /// Oracle ring buffer, `slot0.observation_cardinality_next` entries long once initialized.
+    pub observations: Vec<Observation>,
+    /// Timestamp of the simulated block, at which swaps and position changes write observations.
+    pub block_timestamp: u32,
 }
 
 impl UniswapV3Pool {
-    pub fn from_json_file(path: &str) -> Self {
-        let json_str = fs::read_to_string(path).expect("Cannot read JSON file");
-        let json: serde_json::Value = serde_json::from_str(&json_str).expect("Invalid JSON");
-
-        let pool = &json["pool"]["store"];
-        let token0 = Address::from_str(json["pool"]["token0"].as_str().unwrap()).unwrap();
-        let token1 = Address::from_str(json["pool"]["token1"].as_str().unwrap()).unwrap();
-
-        let fee = U24::from_str_radix(pool["fee"].as_str().unwrap().trim_start_matches("0x"), 16)
-            .unwrap();
-        let tick_spacing = pool["tick_spacing"]
-            .as_str()
-            .unwrap()
-            .parse::<I24>()
-            .unwrap();
-
-        let slot0_obj = &pool["slot0"];
-        let slot0 = Slot0 {
-            sqrt_price_x96: U256::from_str_radix(
-                slot0_obj["sqrt_price_x96"]
-                    .as_str()
-                    .unwrap()
-                    .trim_start_matches("0x"),
-                16,
-            )
-            .unwrap(),
-            tick: slot0_obj["tick"].as_str().unwrap().parse::<i32>().unwrap(),
-            observation_index: 0,
-            observation_cardinality: 0,
-            observation_cardinality_next: 0,
-            fee_protocol: u8::from_str_radix(
-                slot0_obj["fee_protocol"]
-                    .as_str()
-                    .unwrap()
-                    .trim_start_matches("0x"),
-                16,
-            )
-            .unwrap(),
-        };
+    /// Swaps against the current state. Pools tracking the oracle write the observation at
+    /// `block_timestamp`, which isn't advanced by the swap, so callers simulating several blocks
+    /// must set it before each block.
+    pub fn swap(&mut self, params: SwapParams) -> Result<SwapResult, UniswapV3MathError> {
+        let result = self.compute_swap(&params, false)?;
+        self.apply(&result);
+        Ok(result)
+    }
 
-        let fee_growth_global0_x128 = U256::from_str_radix(
-            pool["fee_growth_global_0x128"]
-                .as_str()
-                .unwrap()
-                .trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-        let fee_growth_global1_x128 = U256::from_str_radix(
-            pool["fee_growth_global_1x128"]
-                .as_str()
-                .unwrap()
-                .trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-        let liquidity = u128::from_str_radix(
-            pool["liquidity"].as_str().unwrap().trim_start_matches("0x"),
-            16,
-        )
-        .unwrap();
-
-        let mut tick_bitmap = HashMap::new();
-        if let Some(map) = pool["tick_bitmap"].as_object() {
-            for (k, v) in map.iter() {
-                let key = k.parse::<I16>().unwrap();
-                let value =
-                    U256::from_str_radix(v.as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
-                tick_bitmap.insert(key, value);
-            }
+    /// Same as `swap`, additionally recording every step of the swap loop in `SwapResult::steps`.
+    pub fn swap_traced(&mut self, params: SwapParams) -> Result<SwapResult, UniswapV3MathError> {
+        let result = self.compute_swap(&params, true)?;
+        self.apply(&result);
+        Ok(result)
+    }
+
+    /// Simulates `params` against the current state without modifying the pool.
+    pub fn quote(&self, params: &SwapParams) -> Result<SwapResult, UniswapV3MathError> {
+        self.compute_swap(params, false)
+    }
+
+    pub fn quote_exact_input(
+        &self,
+        zero_for_one: bool,
+        amount_in: I256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        self.quote(&SwapParams::exact_input(
+            Address::ZERO,
+            zero_for_one,
+            amount_in,
+        ))
+    }
+
+    pub fn quote_exact_output(
+        &self,
+        zero_for_one: bool,
+        amount_out: I256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        self.quote(&SwapParams::exact_output(
+            Address::ZERO,
+            zero_for_one,
+            amount_out,
+        ))
+    }
+
+    /// Commits a result returned by `quote`. The result must have been computed against the
+    /// current state of this pool, i.e. no other swap may have been applied in between.
+    pub fn apply(&mut self, result: &SwapResult) {
+        // the observation records the state the pool was in up to this block
+        if result.tick != self.slot0.tick {
+            self.write_observation();
         }
 
-        let mut ticks = HashMap::new();
-        if let Some(map) = pool["ticks"].as_object() {
-            for (k, v) in map.iter() {
-                let key = k.parse::<i32>().unwrap();
-                let liquidity_net = i128::from_str(v["liquidity_net"].as_str().unwrap()).unwrap();
-                let liquidity_gross = u128::from_str_radix(
-                    v["liquidity_gross"]
-                        .as_str()
-                        .unwrap()
-                        .trim_start_matches("0x"),
-                    16,
-                )
-                .unwrap();
-                let fee_growth_outside0_x128 = U256::from_str_radix(
-                    v["fee_growth_outside_0x128"]
-                        .as_str()
-                        .unwrap()
-                        .trim_start_matches("0x"),
-                    16,
-                )
-                .unwrap();
-                let fee_growth_outside1_x128 = U256::from_str_radix(
-                    v["fee_growth_outside_1x128"]
-                        .as_str()
-                        .unwrap()
-                        .trim_start_matches("0x"),
-                    16,
-                )
-                .unwrap();
-                ticks.insert(
-                    key,
-                    TickInfo {
-                        liquidity_gross,
-                        liquidity_net,
-                        fee_growth_outside0_x128,
-                        fee_growth_outside1_x128,
-                    },
-                );
-            }
+        for crossing in &result.ticks_crossed {
+            tick::cross(
+                &mut self.ticks,
+                crossing.tick,
+                crossing.fee_growth_global0_x128,
+                crossing.fee_growth_global1_x128,
+            );
         }
 
-        let protocol_fees = ProtocolFees {
-            token0: u128::from_str_radix(
-                pool["protocol_fees"]["token0"]
-                    .as_str()
-                    .unwrap()
-                    .trim_start_matches("0x"),
-                16,
-            )
-            .unwrap(),
-            token1: u128::from_str_radix(
-                pool["protocol_fees"]["token1"]
-                    .as_str()
-                    .unwrap()
-                    .trim_start_matches("0x"),
-                16,
-            )
-            .unwrap(),
-        };
+        // Update pool state
+        self.slot0.sqrt_price_x96 = result.sqrt_price_x96;
+        self.slot0.tick = result.tick;
+        self.liquidity = result.liquidity;
 
-        Self {
-            token0,
-            token1,
-            fee,
-            tick_spacing,
-            slot0,
-            fee_growth_global0_x128,
-            fee_growth_global1_x128,
-            protocol_fees,
-            liquidity,
-            ticks,
-            tick_bitmap,
+        // update fee growth global and, if necessary, protocol fees
+        if result.zero_for_one {
+            self.fee_growth_global0_x128 = result.fee_growth_global_x128;
+            self.protocol_fees.token0 = self.protocol_fees.token0.wrapping_add(result.protocol_fee);
+        } else {
+            self.fee_growth_global1_x128 = result.fee_growth_global_x128;
+            self.protocol_fees.token1 = self.protocol_fees.token1.wrapping_add(result.protocol_fee);
         }
     }
-    pub fn swap(&mut self, params: SwapParams) -> Result<SwapResult, UniswapV3MathError> {
+
+    fn compute_swap(
+        &self,
+        params: &SwapParams,
+        trace: bool,
+    ) -> Result<SwapResult, UniswapV3MathError> {
         if params.amount_specified == I256::ZERO {
             return Err(UniswapV3MathError::ZeroAmountSpecified);
         }
 
+        let valid_limit = if params.zero_for_one {
+            params.sqrt_price_limit_x96 < self.slot0.sqrt_price_x96
+                && params.sqrt_price_limit_x96 > tick_math::MIN_SQRT_RATIO
+        } else {
+            params.sqrt_price_limit_x96 > self.slot0.sqrt_price_x96
+                && params.sqrt_price_limit_x96 < tick_math::MAX_SQRT_RATIO
+        };
+        if !valid_limit {
+            return Err(UniswapV3MathError::InvalidSqrtPriceLimit);
+        }
+
         let mut amount_specified_remaining = params.amount_specified;
         let mut amount_calculated = I256::ZERO;
         let mut sqrt_price_x96 = self.slot0.sqrt_price_x96;
@@ -189,38 +156,39 @@ impl UniswapV3Pool {
 
         let exact_input = params.amount_specified > I256::ZERO;
 
-        // Collect all initialized tick indices and sort for efficient searching
-        let mut all_ticks: Vec<i32> = self.ticks.keys().copied().collect();
-        all_ticks.sort();
+        // the protocol fee for the input token
+        let fee_protocol_layout = self.metadata.fee_protocol_layout();
+        let fee_protocol =
+            fee_protocol_layout.unpack(self.slot0.fee_protocol, !params.zero_for_one);
+        let mut fee_growth_global_x128 = if params.zero_for_one {
+            self.fee_growth_global0_x128
+        } else {
+            self.fee_growth_global1_x128
+        };
+        let mut protocol_fee = 0u128;
+        let mut fee_amount_total = U256::ZERO;
+        let mut ticks_crossed = Vec::new();
+        let mut steps = trace.then(Vec::new);
+
+        let tick_spacing = self.tick_spacing.as_i32();
 
+        // continue swapping as long as we haven't used the entire input/output and haven't reached the price limit
         while amount_specified_remaining != I256::ZERO
-            && ((params.zero_for_one && sqrt_price_x96 > params.sqrt_price_limit_x96)
-                || (!params.zero_for_one && sqrt_price_x96 < params.sqrt_price_limit_x96))
+            && sqrt_price_x96 != params.sqrt_price_limit_x96
         {
-            // 1. Find the next initialized tick in the direction
-            let next_tick_opt = if params.zero_for_one {
-                all_ticks.iter().filter(|&&t| t < tick).max().copied()
-            } else {
-                all_ticks.iter().filter(|&&t| t > tick).min().copied()
-            };
+            let sqrt_price_start_x96 = sqrt_price_x96;
 
-            // If no more initialized ticks, use boundary tick
-            let (next_tick, sqrt_price_next_x96) = if let Some(next_tick) = next_tick_opt {
-                (
-                    next_tick,
-                    tick_math::get_sqrt_ratio_at_tick(next_tick).unwrap(),
-                )
-            } else if params.zero_for_one {
-                (
-                    tick_math::MIN_TICK,
-                    tick_math::get_sqrt_ratio_at_tick(tick_math::MIN_TICK).unwrap(),
-                )
-            } else {
-                (
-                    tick_math::MAX_TICK,
-                    tick_math::get_sqrt_ratio_at_tick(tick_math::MAX_TICK).unwrap(),
-                )
-            };
+            // 1. Find the next initialized tick in the direction, at most one bitmap word away
+            let (mut next_tick, initialized) = tick_bitmap::next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                tick,
+                tick_spacing,
+                params.zero_for_one,
+            )?;
+
+            // Ensure that we do not overshoot the min/max tick, as the tick bitmap is not aware of these bounds
+            next_tick = next_tick.clamp(tick_math::MIN_TICK, tick_math::MAX_TICK);
+            let sqrt_price_next_x96 = tick_math::get_sqrt_ratio_at_tick(next_tick)?;
 
             // 2. Set the target price for this step
             let target_price_x96 = if params.zero_for_one {
@@ -238,15 +206,14 @@ impl UniswapV3Pool {
             };
 
             // 3. Compute the swap step
-            let (new_sqrt_price_x96, amount_in, amount_out, fee_amount) =
+            let (new_sqrt_price_x96, amount_in, amount_out, mut fee_amount) =
                 swap_math::compute_swap_step(
                     sqrt_price_x96,
                     target_price_x96,
                     liquidity,
                     amount_specified_remaining,
                     self.fee.as_limbs()[0] as u32,
-                )
-                .unwrap();
+                )?;
 
             let step_amount_in = I256::try_from(amount_in).unwrap_or(I256::ZERO);
             let step_amount_out = I256::try_from(amount_out).unwrap_or(I256::ZERO);
@@ -260,26 +227,73 @@ impl UniswapV3Pool {
                 amount_calculated += step_amount_in + step_fee;
             }
 
+            fee_amount_total += fee_amount;
+            if let Some(steps) = steps.as_mut() {
+                steps.push(StepComputations {
+                    sqrt_price_start_x96,
+                    tick_next: next_tick,
+                    initialized,
+                    sqrt_price_next_x96,
+                    amount_in,
+                    amount_out,
+                    fee_amount,
+                    liquidity,
+                });
+            }
+
+            // if the protocol fee is on, calculate how much is owed, decrement fee_amount, and increment protocol_fee
+            if fee_protocol > 0 {
+                let delta = fee_protocol_layout.protocol_fee(fee_protocol, fee_amount);
+                fee_amount -= delta;
+                protocol_fee = protocol_fee.wrapping_add(delta.wrapping_to::<u128>());
+            }
+
+            // update global fee tracker
+            if liquidity > 0 {
+                fee_growth_global_x128 = fee_growth_global_x128.wrapping_add(full_math::mul_div(
+                    fee_amount,
+                    full_math::Q128,
+                    U256::from(liquidity),
+                )?);
+            }
+
             sqrt_price_x96 = new_sqrt_price_x96;
 
             // 4. If we reached the next tick, update liquidity
             if sqrt_price_x96 == sqrt_price_next_x96 {
+                if initialized {
+                    // the tick itself is only flipped once the result is applied
+                    let mut liquidity_net = self
+                        .ticks
+                        .get(&next_tick)
+                        .map_or(0, |info| info.liquidity_net);
+                    // if we're moving leftward, we interpret liquidity_net as the opposite sign
+                    if params.zero_for_one {
+                        liquidity_net = -liquidity_net;
+                    }
+                    liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+                    ticks_crossed.push(TickCrossing {
+                        tick: next_tick,
+                        fee_growth_global0_x128: if params.zero_for_one {
+                            fee_growth_global_x128
+                        } else {
+                            self.fee_growth_global0_x128
+                        },
+                        fee_growth_global1_x128: if params.zero_for_one {
+                            self.fee_growth_global1_x128
+                        } else {
+                            fee_growth_global_x128
+                        },
+                    });
+                }
                 tick = if params.zero_for_one {
                     next_tick - 1
                 } else {
                     next_tick
                 };
-                if let Some(tick_info) = self.ticks.get(&next_tick) {
-                    // Add the signed liquidity_net directly!
-                    if params.zero_for_one {
-                        liquidity = (liquidity as i128 + tick_info.liquidity_net) as u128;
-                    } else {
-                        liquidity = (liquidity as i128 + tick_info.liquidity_net) as u128;
-                    }
-                }
-            } else {
+            } else if sqrt_price_x96 != sqrt_price_start_x96 {
                 // Not crossing a tick, update tick according to current sqrt_price_x96
-                tick = tick_math::get_tick_at_sqrt_ratio(sqrt_price_x96).unwrap();
+                tick = tick_math::get_tick_at_sqrt_ratio(sqrt_price_x96)?;
             }
         }
 
@@ -296,16 +310,55 @@ impl UniswapV3Pool {
             )
         };
 
-        // Update pool state
-        self.slot0.sqrt_price_x96 = sqrt_price_x96;
-        self.slot0.tick = tick;
-        self.liquidity = liquidity;
-
-        Ok(SwapResult { amount0, amount1 })
+        Ok(SwapResult {
+            amount0,
+            amount1,
+            sqrt_price_x96,
+            tick,
+            liquidity,
+            fee_amount: fee_amount_total,
+            ticks_crossed,
+            steps,
+            zero_for_one: params.zero_for_one,
+            fee_growth_global_x128,
+            protocol_fee,
+        })
     }
 }
 
 fn main() {}
 
+#[cfg(test)]
+mod arbitrage_test;
+#[cfg(test)]
+mod callback_test;
+#[cfg(test)]
+mod depth_test;
+#[cfg(test)]
+mod events_test;
+#[cfg(all(test, feature = "alloy"))]
+mod fetch_test;
+#[cfg(test)]
+mod flash_test;
+#[cfg(test)]
+mod ledger_test;
+#[cfg(test)]
+mod logs_test;
+#[cfg(test)]
+mod observations_test;
+#[cfg(test)]
+mod position_test;
+#[cfg(test)]
+mod price_target_test;
+#[cfg(test)]
+mod quoter_test;
+#[cfg(test)]
+mod router_test;
+#[cfg(test)]
+mod snapshot_store_test;
+#[cfg(test)]
+mod snapshot_test;
 #[cfg(test)]
 mod swap_test;
+#[cfg(test)]
+mod test_utils;
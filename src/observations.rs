@@ -0,0 +1,67 @@
+use alloy_primitives::U256;
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::oracle;
+
+impl UniswapV3Pool {
+    /// Starts the oracle at `block_timestamp`, replacing any existing observations. Pools loaded
+    /// from snapshots without observations don't track the oracle until this is called.
+    pub fn initialize_oracle(&mut self) {
+        self.observations.clear();
+        let (cardinality, cardinality_next) =
+            oracle::initialize(&mut self.observations, self.block_timestamp);
+        self.slot0.observation_index = 0;
+        self.slot0.observation_cardinality = cardinality;
+        self.slot0.observation_cardinality_next = cardinality_next;
+    }
+
+    /// Grows the observation buffer to hold at least `observation_cardinality_next` entries.
+    pub fn increase_observation_cardinality_next(
+        &mut self,
+        observation_cardinality_next: u16,
+    ) -> Result<(), UniswapV3MathError> {
+        self.slot0.observation_cardinality_next = oracle::grow(
+            &mut self.observations,
+            self.slot0.observation_cardinality_next,
+            observation_cardinality_next,
+        )?;
+        Ok(())
+    }
+
+    /// Returns the tick and seconds per liquidity cumulatives as of each `seconds_agos` before
+    /// `block_timestamp`.
+    pub fn observe(
+        &self,
+        seconds_agos: &[u32],
+    ) -> Result<(Vec<i64>, Vec<U256>), UniswapV3MathError> {
+        oracle::observe(
+            &self.observations,
+            self.block_timestamp,
+            seconds_agos,
+            self.slot0.tick,
+            self.slot0.observation_index,
+            self.liquidity,
+            self.slot0.observation_cardinality,
+        )
+    }
+
+    pub(crate) fn write_observation(&mut self) {
+        if self.slot0.observation_cardinality == 0 {
+            return;
+        }
+
+        (
+            self.slot0.observation_index,
+            self.slot0.observation_cardinality,
+        ) = oracle::write(
+            &mut self.observations,
+            self.slot0.observation_index,
+            self.block_timestamp,
+            self.slot0.tick,
+            self.liquidity,
+            self.slot0.observation_cardinality,
+            self.slot0.observation_cardinality_next,
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use super::*;
+use crate::test_utils::{SNAPSHOT, i256, owner};
+
+fn oracle_pool() -> UniswapV3Pool {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.block_timestamp = 1000;
+    pool.initialize_oracle();
+    pool.increase_observation_cardinality_next(10).unwrap();
+    pool
+}
+
+#[test]
+fn test_oracle_not_initialized() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    assert!(pool.observations.is_empty());
+    assert!(matches!(
+        pool.observe(&[0]),
+        Err(UniswapV3MathError::OracleNotInitialized)
+    ));
+
+    // swaps don't need the oracle
+    let params = SwapParams::exact_input(owner(), false, i256("1000000"));
+    pool.swap(params).unwrap();
+    assert!(pool.observations.is_empty());
+    assert_eq!(pool.slot0.observation_cardinality, 0);
+}
+
+#[test]
+fn test_initialize_and_grow() {
+    let pool = oracle_pool();
+    assert_eq!(pool.slot0.observation_index, 0);
+    assert_eq!(pool.slot0.observation_cardinality, 1);
+    assert_eq!(pool.slot0.observation_cardinality_next, 10);
+    assert_eq!(pool.observations.len(), 10);
+    assert_eq!(
+        pool.observations[0],
+        Observation {
+            block_timestamp: 1000,
+            tick_cumulative: 0,
+            seconds_per_liquidity_cumulative_x128: U256::ZERO,
+            initialized: true,
+        }
+    );
+
+    let (tick_cumulatives, _) = pool.observe(&[0]).unwrap();
+    assert_eq!(tick_cumulatives, vec![0]);
+}
+
+#[test]
+fn test_swap_writes_observation() {
+    let mut pool = oracle_pool();
+    let tick = pool.slot0.tick;
+    let liquidity = pool.liquidity;
+    assert_eq!(tick, 20603);
+
+    pool.block_timestamp = 1012;
+    let params = SwapParams::exact_input(owner(), false, i256("1000000000000000000000000"));
+    pool.swap(params).unwrap();
+    assert_eq!(pool.slot0.tick, 26365);
+    assert_eq!(pool.slot0.observation_index, 1);
+    assert_eq!(pool.slot0.observation_cardinality, 10);
+
+    // the observation accumulates the state before the swap
+    let seconds_per_liquidity = (U256::from(12) << 128) / U256::from(liquidity);
+    assert_eq!(
+        pool.observations[1],
+        Observation {
+            block_timestamp: 1012,
+            tick_cumulative: 20603 * 12,
+            seconds_per_liquidity_cumulative_x128: seconds_per_liquidity,
+            initialized: true,
+        }
+    );
+
+    // a second swap in the same block doesn't write another observation
+    let params = SwapParams::exact_input(owner(), true, i256("1000000"));
+    pool.swap(params).unwrap();
+    assert_eq!(pool.slot0.observation_index, 1);
+
+    pool.block_timestamp = 1030;
+    let (tick_cumulatives, seconds_per_liquidity_cumulatives) = pool.observe(&[0, 18, 30]).unwrap();
+    assert_eq!(
+        tick_cumulatives,
+        vec![20603 * 12 + pool.slot0.tick as i64 * 18, 20603 * 12, 0]
+    );
+    assert_eq!(
+        seconds_per_liquidity_cumulatives,
+        vec![
+            seconds_per_liquidity + (U256::from(18) << 128) / U256::from(pool.liquidity),
+            seconds_per_liquidity,
+            U256::ZERO
+        ]
+    );
+
+    // interpolates between observations
+    let (tick_cumulatives, _) = pool.observe(&[24]).unwrap();
+    assert_eq!(tick_cumulatives, vec![20603 * 6]);
+
+    assert!(matches!(
+        pool.observe(&[31]),
+        Err(UniswapV3MathError::ObservationTooOld)
+    ));
+}
+
+#[test]
+fn test_mint_writes_observation() {
+    let mut pool = oracle_pool();
+
+    // out of range mints leave the in range liquidity unchanged
+    pool.block_timestamp = 1005;
+    pool.mint(owner(), 30000, 31000, 10u128.pow(21)).unwrap();
+    assert_eq!(pool.slot0.observation_index, 0);
+
+    pool.mint(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    assert_eq!(pool.slot0.observation_index, 1);
+    assert_eq!(pool.observations[1].block_timestamp, 1005);
+    assert_eq!(pool.observations[1].tick_cumulative, 20603 * 5);
+}
+
+#[test]
+fn test_snapshot_round_trip_with_oracle() {
+    let mut pool = oracle_pool();
+    pool.block_timestamp = 1012;
+    let params = SwapParams::exact_input(owner(), false, i256("1000000000000000000000000"));
+    pool.swap(params).unwrap();
+    assert_eq!(pool.slot0.observation_cardinality, 10);
+
+    let json = pool.to_snapshot().to_json_string().unwrap();
+    let loaded = UniswapV3Pool::from_json_str(&json).unwrap();
+    assert_eq!(loaded.observations, pool.observations);
+    assert_eq!(loaded.block_timestamp, 1012);
+    assert_eq!(loaded.slot0.observation_index, pool.slot0.observation_index);
+    assert_eq!(loaded.slot0.observation_cardinality, 10);
+    assert_eq!(loaded.slot0.observation_cardinality_next, 10);
+    assert_eq!(
+        loaded.observe(&[0, 12]).unwrap(),
+        pool.observe(&[0, 12]).unwrap()
+    );
+}
@@ -0,0 +1,87 @@
+use alloy_primitives::aliases::U24;
+use alloy_primitives::{Address, Bytes};
+use thiserror::Error;
+
+const ADDR_SIZE: usize = 20;
+const FEE_SIZE: usize = 3;
+const NEXT_OFFSET: usize = ADDR_SIZE + FEE_SIZE;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PathError {
+    #[error("path has {0} bytes, expected 20 bytes per token and 3 bytes per fee")]
+    Length(usize),
+    #[error("path has {tokens} tokens and {fees} fees, expected one more token than fees")]
+    Hops { tokens: usize, fees: usize },
+}
+
+/// A swap path as encoded by the periphery: `token, fee, token, fee, …, token`, where each fee
+/// selects the pool between the tokens around it. Exact output paths are listed from the output
+/// token to the input token, as in the periphery.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Path {
+    tokens: Vec<Address>,
+    fees: Vec<U24>,
+}
+
+impl Path {
+    pub fn new(tokens: Vec<Address>, fees: Vec<U24>) -> Result<Self, PathError> {
+        if fees.is_empty() || tokens.len() != fees.len() + 1 {
+            return Err(PathError::Hops {
+                tokens: tokens.len(),
+                fees: fees.len(),
+            });
+        }
+        Ok(Self { tokens, fees })
+    }
+
+    pub fn decode(path: &[u8]) -> Result<Self, PathError> {
+        if path.len() < ADDR_SIZE + NEXT_OFFSET
+            || !(path.len() - ADDR_SIZE).is_multiple_of(NEXT_OFFSET)
+        {
+            return Err(PathError::Length(path.len()));
+        }
+
+        let mut tokens = vec![Address::from_slice(&path[..ADDR_SIZE])];
+        let mut fees = Vec::new();
+        for hop in path[ADDR_SIZE..].chunks(NEXT_OFFSET) {
+            fees.push(U24::from_be_slice(&hop[..FEE_SIZE]));
+            tokens.push(Address::from_slice(&hop[FEE_SIZE..]));
+        }
+        Ok(Self { tokens, fees })
+    }
+
+    pub fn encode(&self) -> Bytes {
+        let mut path = Vec::with_capacity(ADDR_SIZE + self.fees.len() * NEXT_OFFSET);
+        path.extend_from_slice(self.tokens[0].as_slice());
+        for (fee, token) in self.fees.iter().zip(&self.tokens[1..]) {
+            path.extend_from_slice(&fee.to_be_bytes::<FEE_SIZE>());
+            path.extend_from_slice(token.as_slice());
+        }
+        path.into()
+    }
+
+    pub fn tokens(&self) -> &[Address] {
+        &self.tokens
+    }
+
+    pub fn fees(&self) -> &[U24] {
+        &self.fees
+    }
+
+    /// The pools along the path as `(token, fee, token)`, in path order.
+    pub fn hops(&self) -> impl Iterator<Item = (Address, U24, Address)> + '_ {
+        self.fees
+            .iter()
+            .enumerate()
+            .map(|(i, &fee)| (self.tokens[i], fee, self.tokens[i + 1]))
+    }
+
+    /// The same pools in the opposite direction, turning an exact input path into an exact
+    /// output path and back.
+    pub fn reversed(&self) -> Self {
+        Self {
+            tokens: self.tokens.iter().rev().copied().collect(),
+            fees: self.fees.iter().rev().copied().collect(),
+        }
+    }
+}
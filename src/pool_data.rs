@@ -1,4 +1,42 @@
 use alloy_primitives::{Address, Bytes, I256, U256};
+use std::fmt;
+
+use crate::libraries::tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+
+/// Where a pool's state was taken from, as recorded in its snapshot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolMetadata {
+    pub address: Address,
+    /// Block at which the state was read.
+    pub state_block: u64,
+    /// Human readable name, e.g. `Pan.V3.USD1.MERL`.
+    pub name: String,
+    /// DEX family, e.g. `Pancake`.
+    pub dex: String,
+    /// Protocol of the pool, e.g. `V3`.
+    pub protocol: String,
+    /// Version of the stored state, e.g. `v3`.
+    pub version: String,
+    /// Contract the stored state belongs to, e.g. `V3Pool`.
+    pub store_protocol: String,
+}
+
+impl PoolMetadata {
+    /// How the pool packs `Slot0::fee_protocol`, which only PancakeSwap changed.
+    pub fn fee_protocol_layout(&self) -> FeeProtocolLayout {
+        if self.dex == "Pancake" {
+            FeeProtocolLayout::Pancake
+        } else {
+            FeeProtocolLayout::Uniswap
+        }
+    }
+}
+
+impl fmt::Display for PoolMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?} @ {}", self.name, self.address, self.state_block)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Slot0 {
@@ -7,7 +45,58 @@ pub struct Slot0 {
     pub observation_index: u16,
     pub observation_cardinality: u16,
     pub observation_cardinality_next: u16,
-    pub fee_protocol: u8,
+    /// Share of the swap and flash fees kept by the protocol, for token0 and token1 as packed
+    /// by the pool's `FeeProtocolLayout`. A uint8 on Uniswap and a uint32 on PancakeSwap.
+    pub fee_protocol: u32,
+}
+
+/// How the fee protocols of token0 and token1 are packed into `Slot0::fee_protocol`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeProtocolLayout {
+    /// A nibble per token, token0's being the low one. Each holds the denominator of the share
+    /// of the fee kept by the protocol, from 4 to 10, or 0 for none.
+    Uniswap,
+    /// 16 bits per token, token0's being the low ones. Each holds the share of the fee kept by
+    /// the protocol in basis points, from 1000 to 4000, or 0 for none.
+    Pancake,
+}
+
+impl FeeProtocolLayout {
+    /// Packs the fee protocols of token0 and token1, or returns `None` if either is one the
+    /// pool's `setFeeProtocol` rejects.
+    pub fn pack(self, fee_protocol0: u32, fee_protocol1: u32) -> Option<u32> {
+        let (valid, shift) = match self {
+            Self::Uniswap => (4..=10, 4),
+            Self::Pancake => (1000..=4000, 16),
+        };
+        let valid = |fee_protocol| fee_protocol == 0 || valid.contains(&fee_protocol);
+        (valid(fee_protocol0) && valid(fee_protocol1))
+            .then_some(fee_protocol0 | (fee_protocol1 << shift))
+    }
+
+    /// The fee protocol of token1 if `token1` is set, else of token0.
+    pub fn unpack(self, fee_protocol: u32, token1: bool) -> u32 {
+        let bits = match self {
+            Self::Uniswap => 4,
+            Self::Pancake => 16,
+        };
+        if token1 {
+            fee_protocol >> bits
+        } else {
+            fee_protocol % (1 << bits)
+        }
+    }
+
+    /// Part of `fee_amount` kept by the protocol under the unpacked `fee_protocol` of its token.
+    pub fn protocol_fee(self, fee_protocol: u32, fee_amount: U256) -> U256 {
+        if fee_protocol == 0 {
+            return U256::ZERO;
+        }
+        match self {
+            Self::Uniswap => fee_amount / U256::from(fee_protocol),
+            Self::Pancake => fee_amount * U256::from(fee_protocol) / U256::from(10_000),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -24,6 +113,27 @@ pub struct TickInfo {
     pub fee_growth_outside1_x128: U256,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    /// Tick multiplied by the seconds elapsed since the oracle was initialized, an int56 on chain.
+    pub tick_cumulative: i64,
+    /// Seconds elapsed divided by the in range liquidity, a uint160 on chain.
+    pub seconds_per_liquidity_cumulative_x128: U256,
+    pub initialized: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionInfo {
+    pub liquidity: u128,
+    /// Fee growth per unit of liquidity inside the range as of the last update.
+    pub fee_growth_inside0_last_x128: U256,
+    pub fee_growth_inside1_last_x128: U256,
+    /// Fees and burned liquidity owed to the owner, withdrawn with `collect`.
+    pub tokens_owed0: u128,
+    pub tokens_owed1: u128,
+}
+
 #[derive(Clone, Debug)]
 pub struct SwapParams {
     pub recipient: Address,
@@ -33,8 +143,101 @@ pub struct SwapParams {
     pub data: Bytes,
 }
 
+impl SwapParams {
+    /// Swaps exactly `amount_in` of the input token without a price limit.
+    pub fn exact_input(recipient: Address, zero_for_one: bool, amount_in: I256) -> Self {
+        Self {
+            recipient,
+            zero_for_one,
+            amount_specified: amount_in,
+            sqrt_price_limit_x96: Self::no_sqrt_price_limit(zero_for_one),
+            data: Bytes::new(),
+        }
+    }
+
+    /// Swaps for exactly `amount_out` of the output token without a price limit.
+    pub fn exact_output(recipient: Address, zero_for_one: bool, amount_out: I256) -> Self {
+        Self {
+            recipient,
+            zero_for_one,
+            amount_specified: -amount_out,
+            sqrt_price_limit_x96: Self::no_sqrt_price_limit(zero_for_one),
+            data: Bytes::new(),
+        }
+    }
+
+    /// The most extreme price limit the pool accepts in the given direction.
+    pub fn no_sqrt_price_limit(zero_for_one: bool) -> U256 {
+        if zero_for_one {
+            MIN_SQRT_RATIO + U256::from(1)
+        } else {
+            MAX_SQRT_RATIO - U256::from(1)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModifyPositionParams {
+    /// The address that owns the position.
+    pub owner: Address,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Positive when liquidity is minted, negative when it is burned.
+    pub liquidity_delta: i128,
+}
+
 #[derive(Clone, Debug)]
 pub struct SwapResult {
     pub amount0: I256,
     pub amount1: I256,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    /// Total fee paid in the input token, including the protocol share.
+    pub fee_amount: U256,
+    pub ticks_crossed: Vec<TickCrossing>,
+    /// Only recorded by `UniswapV3Pool::swap_traced`.
+    pub steps: Option<Vec<StepComputations>>,
+    pub zero_for_one: bool,
+    /// Fee growth of the input token after the swap.
+    pub fee_growth_global_x128: U256,
+    /// Protocol share of `fee_amount`.
+    pub protocol_fee: u128,
+}
+
+#[derive(Clone, Debug)]
+pub struct TickCrossing {
+    pub tick: i32,
+    pub fee_growth_global0_x128: U256,
+    pub fee_growth_global1_x128: U256,
+}
+
+#[derive(Clone, Debug)]
+pub struct StepComputations {
+    pub sqrt_price_start_x96: U256,
+    pub tick_next: i32,
+    pub initialized: bool,
+    pub sqrt_price_next_x96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+    /// Liquidity in range while the step was computed, before crossing `tick_next`.
+    pub liquidity: u128,
+}
+
+/// Amounts of the swap that moves the price to a target, see
+/// `UniswapV3Pool::amount_to_reach_sqrt_price`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetAmounts {
+    pub zero_for_one: bool,
+    /// Input to swap, including `fee_amount`.
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// Fee paid in the input token, including the protocol share.
+    pub fee_amount: U256,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    /// The initialized ticks crossed on the way, in the order they are crossed.
+    pub ticks_crossed: Vec<i32>,
 }
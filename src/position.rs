@@ -0,0 +1,394 @@
+use alloy_primitives::{Address, I256, U256};
+
+use crate::UniswapV3Pool;
+use crate::callback::Balances;
+use crate::ledger::LedgerError;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::{liquidity_math, position, sqrt_price_math, tick, tick_bitmap, tick_math};
+use crate::pool_data::ModifyPositionParams;
+
+impl UniswapV3Pool {
+    /// Adds `amount` of liquidity to the position of `owner` and returns the amounts of token0
+    /// and token1 that have to be paid for it.
+    pub fn mint(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        if amount == 0 {
+            return Err(UniswapV3MathError::ZeroAmountSpecified);
+        }
+
+        let (amount0, amount1) = self.modify_position(&ModifyPositionParams {
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity_delta: i128::try_from(amount)
+                .map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?,
+        })?;
+
+        Ok((amount0.into_raw(), amount1.into_raw()))
+    }
+
+    /// Same as `mint`, additionally paying the amounts owed from `payer` to the pool, as
+    /// `uniswapV3MintCallback` does. Fails without any changes if `payer` can't pay them.
+    pub fn mint_paid_by(
+        &mut self,
+        balances: &mut dyn Balances,
+        payer: Address,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(U256, U256), LedgerError> {
+        let (amount0, amount1, _) = self.position_amounts(&ModifyPositionParams {
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity_delta: i128::try_from(amount)
+                .map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?,
+        })?;
+        let payments = [
+            (self.token0, amount0.into_raw()),
+            (self.token1, amount1.into_raw()),
+        ];
+        for (token, amount) in payments {
+            if balances.balance_of(token, payer) < amount {
+                return Err(LedgerError::InsufficientBalance {
+                    token,
+                    holder: payer,
+                });
+            }
+        }
+
+        let amounts = self.mint(owner, tick_lower, tick_upper, amount)?;
+        for (token, amount) in payments {
+            if amount > U256::ZERO {
+                balances.transfer(token, payer, self.metadata.address, amount)?;
+            }
+        }
+
+        Ok(amounts)
+    }
+
+    /// Removes `amount` of liquidity from the position of `owner` and returns the amounts of
+    /// token0 and token1 released by it. They are added to the tokens owed to the position, along
+    /// with the fees earned so far. Burning 0 only updates the fees owed.
+    pub fn burn(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let (amount0, amount1) = self.modify_position(&ModifyPositionParams {
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity_delta: -i128::try_from(amount)
+                .map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?,
+        })?;
+        let (amount0, amount1) = ((-amount0).into_raw(), (-amount1).into_raw());
+
+        if amount0 > U256::ZERO || amount1 > U256::ZERO {
+            let position = self
+                .positions
+                .entry((owner, tick_lower, tick_upper))
+                .or_default();
+            position.tokens_owed0 = position
+                .tokens_owed0
+                .wrapping_add(amount0.wrapping_to::<u128>());
+            position.tokens_owed1 = position
+                .tokens_owed1
+                .wrapping_add(amount1.wrapping_to::<u128>());
+        }
+
+        Ok((amount0, amount1))
+    }
+
+    /// Withdraws up to the requested amounts from the tokens owed to the position of `owner` and
+    /// returns the amounts withdrawn.
+    pub fn collect(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0_requested: u128,
+        amount1_requested: u128,
+    ) -> (u128, u128) {
+        let Some(position) = self.positions.get_mut(&(owner, tick_lower, tick_upper)) else {
+            return (0, 0);
+        };
+
+        let amount0 = amount0_requested.min(position.tokens_owed0);
+        let amount1 = amount1_requested.min(position.tokens_owed1);
+        position.tokens_owed0 -= amount0;
+        position.tokens_owed1 -= amount1;
+
+        (amount0, amount1)
+    }
+
+    /// Same as `collect`, additionally transferring the amounts withdrawn from the pool to
+    /// `recipient`. Burned liquidity only leaves the pool once collected. Fails without any
+    /// changes if the pool doesn't hold the amounts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn collect_to(
+        &mut self,
+        balances: &mut dyn Balances,
+        recipient: Address,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount0_requested: u128,
+        amount1_requested: u128,
+    ) -> Result<(u128, u128), LedgerError> {
+        let pool = self.metadata.address;
+        let owed = self
+            .positions
+            .get(&(owner, tick_lower, tick_upper))
+            .map_or((0, 0), |position| {
+                (position.tokens_owed0, position.tokens_owed1)
+            });
+        let payments = [
+            (self.token0, amount0_requested.min(owed.0)),
+            (self.token1, amount1_requested.min(owed.1)),
+        ];
+        for (token, amount) in payments {
+            if balances.balance_of(token, pool) < U256::from(amount) {
+                return Err(LedgerError::InsufficientBalance {
+                    token,
+                    holder: pool,
+                });
+            }
+        }
+
+        let amounts = self.collect(
+            owner,
+            tick_lower,
+            tick_upper,
+            amount0_requested,
+            amount1_requested,
+        );
+        for (token, amount) in payments {
+            if amount > 0 {
+                balances.transfer(token, pool, recipient, U256::from(amount))?;
+            }
+        }
+
+        Ok(amounts)
+    }
+
+    /// Withdraws up to the requested amounts from the accrued protocol fees and returns the
+    /// amounts withdrawn.
+    pub fn collect_protocol(
+        &mut self,
+        amount0_requested: u128,
+        amount1_requested: u128,
+    ) -> (u128, u128) {
+        let mut amount0 = amount0_requested.min(self.protocol_fees.token0);
+        let mut amount1 = amount1_requested.min(self.protocol_fees.token1);
+
+        if amount0 > 0 {
+            // ensure that the slot is not cleared, for gas savings
+            if amount0 == self.protocol_fees.token0 {
+                amount0 -= 1;
+            }
+            self.protocol_fees.token0 -= amount0;
+        }
+        if amount1 > 0 {
+            // ensure that the slot is not cleared, for gas savings
+            if amount1 == self.protocol_fees.token1 {
+                amount1 -= 1;
+            }
+            self.protocol_fees.token1 -= amount1;
+        }
+
+        (amount0, amount1)
+    }
+
+    /// The fee growth per unit of liquidity inside the range, as used to compute the fees owed
+    /// to positions.
+    pub fn get_fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        tick::get_fee_growth_inside(
+            &self.ticks,
+            tick_lower,
+            tick_upper,
+            self.slot0.tick,
+            self.fee_growth_global0_x128,
+            self.fee_growth_global1_x128,
+        )
+    }
+
+    fn check_ticks(&self, tick_lower: i32, tick_upper: i32) -> Result<(), UniswapV3MathError> {
+        if tick_lower >= tick_upper {
+            return Err(UniswapV3MathError::TickLowerNotBelowUpper);
+        }
+        if tick_lower < tick_math::MIN_TICK {
+            return Err(UniswapV3MathError::TickLowerTooLow);
+        }
+        if tick_upper > tick_math::MAX_TICK {
+            return Err(UniswapV3MathError::TickUpperTooHigh);
+        }
+
+        let tick_spacing = self.tick_spacing.as_i32();
+        if tick_lower % tick_spacing != 0 || tick_upper % tick_spacing != 0 {
+            return Err(UniswapV3MathError::TickSpacingError);
+        }
+
+        Ok(())
+    }
+
+    /// Applies a liquidity change to the ticks and, if the range is active, to the pool
+    /// liquidity. Returns the amounts owed to the pool, negative when owed to the owner.
+    fn modify_position(
+        &mut self,
+        params: &ModifyPositionParams,
+    ) -> Result<(I256, I256), UniswapV3MathError> {
+        let (amount0, amount1, liquidity) = self.position_amounts(params)?;
+
+        self.update_position(params)?;
+        // the in range liquidity changes, record the time it was active for
+        if liquidity != self.liquidity {
+            self.write_observation();
+        }
+        self.liquidity = liquidity;
+
+        Ok((amount0, amount1))
+    }
+
+    /// The amounts owed for a liquidity change and the pool liquidity after it, without applying
+    /// the change.
+    fn position_amounts(
+        &self,
+        params: &ModifyPositionParams,
+    ) -> Result<(I256, I256, u128), UniswapV3MathError> {
+        self.check_ticks(params.tick_lower, params.tick_upper)?;
+
+        let sqrt_ratio_lower_x96 = tick_math::get_sqrt_ratio_at_tick(params.tick_lower)?;
+        let sqrt_ratio_upper_x96 = tick_math::get_sqrt_ratio_at_tick(params.tick_upper)?;
+
+        let mut amount0 = I256::ZERO;
+        let mut amount1 = I256::ZERO;
+        let mut liquidity = self.liquidity;
+
+        if params.liquidity_delta != 0 {
+            if self.slot0.tick < params.tick_lower {
+                // current tick is below the passed range; liquidity can only become in range by crossing from left to
+                // right, when we'll need _more_ token0 (it's becoming more valuable) so user must provide it
+                amount0 = sqrt_price_math::get_amount_0_delta(
+                    sqrt_ratio_lower_x96,
+                    sqrt_ratio_upper_x96,
+                    params.liquidity_delta,
+                )?;
+            } else if self.slot0.tick < params.tick_upper {
+                // current tick is inside the passed range
+                amount0 = sqrt_price_math::get_amount_0_delta(
+                    self.slot0.sqrt_price_x96,
+                    sqrt_ratio_upper_x96,
+                    params.liquidity_delta,
+                )?;
+                amount1 = sqrt_price_math::get_amount_1_delta(
+                    sqrt_ratio_lower_x96,
+                    self.slot0.sqrt_price_x96,
+                    params.liquidity_delta,
+                )?;
+                liquidity = liquidity_math::add_delta(liquidity, params.liquidity_delta)?;
+            } else {
+                // current tick is above the passed range; liquidity can only become in range by crossing from right to
+                // left, when we'll need _more_ token1 (it's becoming more valuable) so user must provide it
+                amount1 = sqrt_price_math::get_amount_1_delta(
+                    sqrt_ratio_lower_x96,
+                    sqrt_ratio_upper_x96,
+                    params.liquidity_delta,
+                )?;
+            }
+        }
+
+        Ok((amount0, amount1, liquidity))
+    }
+
+    fn update_position(&mut self, params: &ModifyPositionParams) -> Result<(), UniswapV3MathError> {
+        let saved_ticks = [params.tick_lower, params.tick_upper]
+            .map(|tick| (tick, self.ticks.get(&tick).cloned()));
+        let (flipped_lower, flipped_upper) =
+            self.update_ticks_and_position(params).inspect_err(|_| {
+                // leave the ticks untouched if the position could not be updated
+                for (tick, info) in &saved_ticks {
+                    match info {
+                        Some(info) => self.ticks.insert(*tick, info.clone()),
+                        None => self.ticks.remove(tick),
+                    };
+                }
+            })?;
+
+        let tick_spacing = self.tick_spacing.as_i32();
+        if flipped_lower {
+            tick_bitmap::flip_tick(&mut self.tick_bitmap, params.tick_lower, tick_spacing)?;
+        }
+        if flipped_upper {
+            tick_bitmap::flip_tick(&mut self.tick_bitmap, params.tick_upper, tick_spacing)?;
+        }
+
+        // clear any tick data that is no longer needed
+        if params.liquidity_delta < 0 {
+            if flipped_lower {
+                tick::clear(&mut self.ticks, params.tick_lower);
+            }
+            if flipped_upper {
+                tick::clear(&mut self.ticks, params.tick_upper);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_ticks_and_position(
+        &mut self,
+        params: &ModifyPositionParams,
+    ) -> Result<(bool, bool), UniswapV3MathError> {
+        let key = (params.owner, params.tick_lower, params.tick_upper);
+        let mut position = self.positions.get(&key).cloned().unwrap_or_default();
+
+        // if we need to update the ticks, do it
+        let mut flipped_lower = false;
+        let mut flipped_upper = false;
+        if params.liquidity_delta != 0 {
+            let max_liquidity =
+                tick::tick_spacing_to_max_liquidity_per_tick(self.tick_spacing.as_i32());
+            flipped_lower = tick::update(
+                &mut self.ticks,
+                params.tick_lower,
+                self.slot0.tick,
+                params.liquidity_delta,
+                self.fee_growth_global0_x128,
+                self.fee_growth_global1_x128,
+                false,
+                max_liquidity,
+            )?;
+            flipped_upper = tick::update(
+                &mut self.ticks,
+                params.tick_upper,
+                self.slot0.tick,
+                params.liquidity_delta,
+                self.fee_growth_global0_x128,
+                self.fee_growth_global1_x128,
+                true,
+                max_liquidity,
+            )?;
+        }
+
+        let (fee_growth_inside0_x128, fee_growth_inside1_x128) =
+            self.get_fee_growth_inside(params.tick_lower, params.tick_upper);
+        position::update(
+            &mut position,
+            params.liquidity_delta,
+            fee_growth_inside0_x128,
+            fee_growth_inside1_x128,
+        )?;
+        self.positions.insert(key, position);
+
+        Ok((flipped_lower, flipped_upper))
+    }
+}
@@ -0,0 +1,314 @@
+use super::*;
+use crate::test_utils::{SNAPSHOT, owner, u256};
+
+// flipping a bit back leaves an empty word behind, as in the contract storage
+fn initialized_words(pool: &UniswapV3Pool) -> HashMap<I16, U256> {
+    pool.tick_bitmap
+        .iter()
+        .filter(|(_, word)| !word.is_zero())
+        .map(|(&word_pos, &word)| (word_pos, word))
+        .collect()
+}
+
+#[test]
+fn test_mint_and_burn_in_range() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let tick_bitmap = initialized_words(&pool);
+    let liquidity = pool.liquidity;
+
+    let (amount0, amount1) = pool.mint(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    assert_eq!(
+        (amount0, amount1),
+        (u256("7015650276941000067"), u256("83196036273625295708"))
+    );
+    assert_eq!(pool.liquidity, liquidity + 10u128.pow(21));
+
+    // growth below the current tick is attributed to the lower tick, none to the upper tick
+    let lower = &pool.ticks[&20000];
+    assert_eq!(lower.liquidity_gross, 10u128.pow(21));
+    assert_eq!(lower.liquidity_net, 10i128.pow(21));
+    assert_eq!(lower.fee_growth_outside0_x128, pool.fee_growth_global0_x128);
+    assert_eq!(lower.fee_growth_outside1_x128, pool.fee_growth_global1_x128);
+    let upper = &pool.ticks[&21000];
+    assert_eq!(upper.liquidity_gross, 10u128.pow(21));
+    assert_eq!(upper.liquidity_net, -10i128.pow(21));
+    assert_eq!(upper.fee_growth_outside0_x128, U256::ZERO);
+    assert_ne!(initialized_words(&pool), tick_bitmap);
+
+    // burning rounds down
+    let (amount0, amount1) = pool.burn(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    assert_eq!(
+        (amount0, amount1),
+        (u256("7015650276941000066"), u256("83196036273625295707"))
+    );
+    assert_eq!(pool.liquidity, liquidity);
+    assert!(!pool.ticks.contains_key(&20000));
+    assert!(!pool.ticks.contains_key(&21000));
+    assert_eq!(initialized_words(&pool), tick_bitmap);
+}
+
+#[test]
+fn test_mint_out_of_range() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let liquidity = pool.liquidity;
+
+    //above the current tick only token0 is needed
+    let amounts = pool.mint(owner(), 21000, 22000, 10u128.pow(21)).unwrap();
+    assert_eq!(amounts, (u256("17066729209838730207"), U256::ZERO));
+
+    //below the current tick only token1 is needed
+    let amounts = pool.mint(owner(), -1000, 0, 10u128.pow(21)).unwrap();
+    assert_eq!(amounts, (U256::ZERO, u256("48768197581278888999")));
+
+    assert_eq!(pool.liquidity, liquidity);
+    assert_eq!(
+        pool.ticks[&0].fee_growth_outside0_x128,
+        pool.fee_growth_global0_x128
+    );
+    assert_eq!(pool.ticks[&22000].fee_growth_outside0_x128, U256::ZERO);
+}
+
+#[test]
+fn test_mint_on_initialized_tick() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let tick_bitmap = pool.tick_bitmap.clone();
+    let before = pool.ticks[&22082].clone();
+
+    pool.mint(owner(), 22082, 28148, 10u128.pow(21)).unwrap();
+
+    let after = &pool.ticks[&22082];
+    assert_eq!(
+        after.liquidity_gross,
+        before.liquidity_gross + 10u128.pow(21)
+    );
+    assert_eq!(after.liquidity_net, before.liquidity_net + 10i128.pow(21));
+    assert_eq!(
+        after.fee_growth_outside1_x128,
+        before.fee_growth_outside1_x128
+    );
+    assert_eq!(pool.tick_bitmap, tick_bitmap);
+}
+
+#[test]
+fn test_swap_crosses_minted_tick() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let amounts = pool.mint(owner(), 20000, 21000, 10u128.pow(24)).unwrap();
+    assert_eq!(
+        amounts,
+        (
+            u256("7015650276941000066268"),
+            u256("83196036273625295707331")
+        )
+    );
+
+    let result = pool
+        .swap(SwapParams::exact_input(
+            owner(),
+            false,
+            I256::from_dec_str("1000000000000000000000000").unwrap(),
+        ))
+        .unwrap();
+
+    assert_eq!(
+        result.amount0,
+        I256::from_dec_str("-97278956971981123963357").unwrap()
+    );
+    assert_eq!(result.tick, 26096);
+    assert_eq!(result.liquidity, 1124144412144474679681414);
+    assert_eq!(
+        result
+            .ticks_crossed
+            .iter()
+            .map(|crossing| crossing.tick)
+            .collect::<Vec<_>>(),
+        vec![21000, 22082]
+    );
+    assert_eq!(
+        pool.ticks[&21000].fee_growth_outside1_x128,
+        u256("1911682870468520187452654471790920")
+    );
+}
+
+#[test]
+fn test_invalid_ticks() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+
+    let result = pool.mint(owner(), 21000, 20000, 1);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Tick lower must be less than tick upper"
+    );
+    let result = pool.mint(owner(), 20000, 20000, 1);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Tick lower must be less than tick upper"
+    );
+    let result = pool.mint(owner(), tick_math::MIN_TICK - 1, 0, 1);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Tick lower is less than the minimum tick"
+    );
+    let result = pool.mint(owner(), 0, tick_math::MAX_TICK + 1, 1);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Tick upper is greater than the maximum tick"
+    );
+    let result = pool.mint(owner(), 20000, 21000, 0);
+    assert_eq!(result.unwrap_err().to_string(), "Specified amount is 0");
+
+    pool.tick_spacing = I24::try_from(60).unwrap();
+    let result = pool.mint(owner(), 19980, 21001, 1);
+    assert_eq!(result.unwrap_err().to_string(), "Tick spacing error");
+    let result = pool.burn(owner(), 19981, 21000, 1);
+    assert_eq!(result.unwrap_err().to_string(), "Tick spacing error");
+    assert!(!pool.ticks.contains_key(&19980));
+}
+
+#[test]
+fn test_failed_burn_does_not_modify_pool() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.mint(owner(), 20000, 21000, 10u128.pow(21)).unwrap();
+    pool.mint(Address::ZERO, 20000, 21000, 10u128.pow(21))
+        .unwrap();
+    let before = pool.clone();
+
+    // the ticks hold enough liquidity, the position does not
+    let result = pool.burn(owner(), 20000, 21000, 15 * 10u128.pow(20));
+    assert_eq!(result.unwrap_err().to_string(), "Liquidity Sub");
+
+    assert_eq!(pool.liquidity, before.liquidity);
+    assert_eq!(
+        pool.ticks[&20000].liquidity_gross,
+        before.ticks[&20000].liquidity_gross
+    );
+    assert_eq!(
+        pool.ticks[&21000].liquidity_net,
+        before.ticks[&21000].liquidity_net
+    );
+    assert_eq!(
+        pool.positions[&(owner(), 20000, 21000)],
+        before.positions[&(owner(), 20000, 21000)]
+    );
+}
+
+#[test]
+fn test_failed_mint_does_not_modify_pool() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let max_liquidity = tick::tick_spacing_to_max_liquidity_per_tick(1);
+    pool.mint(owner(), 20000, 21000, max_liquidity - 5).unwrap();
+    let before = pool.clone();
+
+    // the lower tick is new, the upper tick would exceed the max liquidity per tick
+    let result = pool.mint(owner(), 19000, 21000, 10);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Liquidity gross exceeds the max liquidity per tick"
+    );
+
+    assert_eq!(pool.liquidity, before.liquidity);
+    assert!(!pool.ticks.contains_key(&19000));
+    assert_eq!(
+        pool.ticks[&21000].liquidity_gross,
+        before.ticks[&21000].liquidity_gross
+    );
+    assert!(!pool.positions.contains_key(&(owner(), 19000, 21000)));
+    assert_eq!(pool.tick_bitmap, before.tick_bitmap);
+}
+
+#[test]
+fn test_fees_owed_and_collect() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.mint(owner(), 20000, 21000, 10u128.pow(24)).unwrap();
+    assert_eq!(
+        pool.get_fee_growth_inside(20000, 21000),
+        (U256::ZERO, U256::ZERO)
+    );
+
+    pool.swap(SwapParams::exact_input(
+        owner(),
+        false,
+        I256::from_dec_str("1000000000000000000000000").unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(
+        pool.get_fee_growth_inside(20000, 21000),
+        (U256::ZERO, u256("1911187702631885155986088490786157"))
+    );
+
+    //poking accrues the fees earned while the range was active
+    assert_eq!(
+        pool.burn(owner(), 20000, 21000, 0).unwrap(),
+        (U256::ZERO, U256::ZERO)
+    );
+    let position = &pool.positions[&(owner(), 20000, 21000)];
+    assert_eq!(position.liquidity, 10u128.pow(24));
+    assert_eq!(
+        (position.tokens_owed0, position.tokens_owed1),
+        (0, 5616475869512017259)
+    );
+
+    //burning adds the released amounts to the tokens owed
+    let (amount0, amount1) = pool.burn(owner(), 20000, 21000, 10u128.pow(24)).unwrap();
+    assert_eq!(
+        (amount0, amount1),
+        (U256::ZERO, u256("139355178492875956281359"))
+    );
+    let tokens_owed1 = 5616475869512017259 + 139355178492875956281359;
+    assert_eq!(
+        pool.positions[&(owner(), 20000, 21000)].tokens_owed1,
+        tokens_owed1
+    );
+
+    //collects at most the requested amounts
+    assert_eq!(
+        pool.collect(owner(), 20000, 21000, 0, 10u128.pow(18)),
+        (0, 10u128.pow(18))
+    );
+    assert_eq!(
+        pool.collect(owner(), 20000, 21000, u128::MAX, u128::MAX),
+        (0, tokens_owed1 - 10u128.pow(18))
+    );
+    assert_eq!(
+        pool.collect(owner(), 20000, 21000, u128::MAX, u128::MAX),
+        (0, 0)
+    );
+
+    //unknown positions owe nothing
+    assert_eq!(
+        pool.collect(Address::ZERO, 20000, 21000, u128::MAX, u128::MAX),
+        (0, 0)
+    );
+
+    //empty positions cannot be poked
+    let result = pool.burn(owner(), 20000, 21000, 0);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Cannot poke a position without liquidity"
+    );
+}
+
+#[test]
+fn test_collect_protocol() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    pool.slot0.fee_protocol = 3300 | (3300 << 16);
+    pool.swap(SwapParams::exact_input(
+        owner(),
+        false,
+        I256::from_dec_str("1000000000000000000000000").unwrap(),
+    ))
+    .unwrap();
+    let protocol_fee = pool.protocol_fees.token1;
+    assert!(protocol_fee > 1);
+
+    assert_eq!(pool.collect_protocol(0, 1), (0, 1));
+    // one unit is always left behind
+    assert_eq!(
+        pool.collect_protocol(u128::MAX, u128::MAX),
+        (0, protocol_fee - 2)
+    );
+    assert_eq!(
+        (pool.protocol_fees.token0, pool.protocol_fees.token1),
+        (0, 1)
+    );
+    assert_eq!(pool.collect_protocol(u128::MAX, u128::MAX), (0, 0));
+}
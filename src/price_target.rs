@@ -0,0 +1,105 @@
+use alloy_primitives::U256;
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::full_math::mul_div_rounding_up;
+use crate::libraries::sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta};
+use crate::libraries::{liquidity_math, tick_bitmap, tick_math};
+use crate::pool_data::TargetAmounts;
+
+impl UniswapV3Pool {
+    /// Computes the swap that moves the price to exactly `sqrt_price_x96`, walking the
+    /// initialized ticks in between the same way `swap` does. Swapping `amount_in` as exact
+    /// input with the target as price limit uses up the whole input, while without the limit
+    /// the last step may overshoot the target by rounding. The amounts are zero if the pool is
+    /// already there.
+    pub fn amount_to_reach_sqrt_price(
+        &self,
+        sqrt_price_x96: U256,
+    ) -> Result<TargetAmounts, UniswapV3MathError> {
+        if sqrt_price_x96 <= tick_math::MIN_SQRT_RATIO
+            || sqrt_price_x96 >= tick_math::MAX_SQRT_RATIO
+        {
+            return Err(UniswapV3MathError::InvalidSqrtPriceLimit);
+        }
+
+        let zero_for_one = sqrt_price_x96 < self.slot0.sqrt_price_x96;
+        let fee = U256::from(self.fee);
+        let tick_spacing = self.tick_spacing.as_i32();
+        let mut amounts = TargetAmounts {
+            zero_for_one,
+            amount_in: U256::ZERO,
+            amount_out: U256::ZERO,
+            fee_amount: U256::ZERO,
+            sqrt_price_x96: self.slot0.sqrt_price_x96,
+            tick: self.slot0.tick,
+            liquidity: self.liquidity,
+            ticks_crossed: Vec::new(),
+        };
+
+        while amounts.sqrt_price_x96 != sqrt_price_x96 {
+            let (next_tick, initialized) = tick_bitmap::next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                amounts.tick,
+                tick_spacing,
+                zero_for_one,
+            )?;
+            let next_tick = next_tick.clamp(tick_math::MIN_TICK, tick_math::MAX_TICK);
+            let sqrt_price_next_x96 = tick_math::get_sqrt_ratio_at_tick(next_tick)?;
+            let step_target_x96 = if zero_for_one {
+                sqrt_price_next_x96.max(sqrt_price_x96)
+            } else {
+                sqrt_price_next_x96.min(sqrt_price_x96)
+            };
+
+            // the input rounds up and the output down, as in a swap step that reaches its target
+            let current = amounts.sqrt_price_x96;
+            let liquidity = amounts.liquidity;
+            let (amount_in, amount_out) = if zero_for_one {
+                (
+                    _get_amount_0_delta(step_target_x96, current, liquidity, true)?,
+                    _get_amount_1_delta(step_target_x96, current, liquidity, false)?,
+                )
+            } else {
+                (
+                    _get_amount_1_delta(current, step_target_x96, liquidity, true)?,
+                    _get_amount_0_delta(current, step_target_x96, liquidity, false)?,
+                )
+            };
+            let fee_amount = mul_div_rounding_up(amount_in, fee, U256::from(1_000_000) - fee)?;
+            amounts.amount_in += amount_in + fee_amount;
+            amounts.amount_out += amount_out;
+            amounts.fee_amount += fee_amount;
+            amounts.sqrt_price_x96 = step_target_x96;
+
+            if step_target_x96 == sqrt_price_next_x96 {
+                if initialized {
+                    let mut liquidity_net = self
+                        .ticks
+                        .get(&next_tick)
+                        .map_or(0, |info| info.liquidity_net);
+                    if zero_for_one {
+                        liquidity_net = -liquidity_net;
+                    }
+                    amounts.liquidity =
+                        liquidity_math::add_delta(amounts.liquidity, liquidity_net)?;
+                    amounts.ticks_crossed.push(next_tick);
+                }
+                amounts.tick = if zero_for_one {
+                    next_tick - 1
+                } else {
+                    next_tick
+                };
+            } else {
+                amounts.tick = tick_math::get_tick_at_sqrt_ratio(step_target_x96)?;
+            }
+        }
+
+        Ok(amounts)
+    }
+
+    /// Computes the swap that moves the price to the start of `tick`.
+    pub fn amount_to_reach_tick(&self, tick: i32) -> Result<TargetAmounts, UniswapV3MathError> {
+        self.amount_to_reach_sqrt_price(tick_math::get_sqrt_ratio_at_tick(tick)?)
+    }
+}
@@ -0,0 +1,96 @@
+use super::*;
+use crate::test_utils::{SNAPSHOT, owner};
+
+/// Swaps the amount to reach `target` up to it and checks the whole amount is used.
+fn assert_reaches(pool: &UniswapV3Pool, target: U256) -> TargetAmounts {
+    let amounts = pool.amount_to_reach_sqrt_price(target).unwrap();
+    let mut swapped = pool.clone();
+    let mut params = SwapParams::exact_input(
+        owner(),
+        amounts.zero_for_one,
+        I256::from_raw(amounts.amount_in),
+    );
+    params.sqrt_price_limit_x96 = target;
+    let result = swapped.swap(params).unwrap();
+
+    assert_eq!(result.sqrt_price_x96, target);
+    assert_eq!(result.tick, amounts.tick);
+    assert_eq!(result.liquidity, amounts.liquidity);
+    assert_eq!(result.fee_amount, amounts.fee_amount);
+    let (amount_in, amount_out) = if amounts.zero_for_one {
+        (result.amount0, -result.amount1)
+    } else {
+        (result.amount1, -result.amount0)
+    };
+    assert_eq!(amount_in.into_raw(), amounts.amount_in);
+    assert_eq!(amount_out.into_raw(), amounts.amount_out);
+    assert_eq!(
+        result
+            .ticks_crossed
+            .iter()
+            .map(|crossing| crossing.tick)
+            .collect::<Vec<_>>(),
+        amounts.ticks_crossed
+    );
+    amounts
+}
+
+#[test]
+fn test_amount_to_reach_tick() {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+
+    for tick in [-100000, 0, 19000, 20603, 20604, 22500, 100000] {
+        let amounts = pool.amount_to_reach_tick(tick).unwrap();
+        assert_eq!(
+            amounts,
+            assert_reaches(&pool, tick_math::get_sqrt_ratio_at_tick(tick).unwrap())
+        );
+    }
+    assert!(
+        !pool
+            .amount_to_reach_tick(100000)
+            .unwrap()
+            .ticks_crossed
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_amount_to_reach_sqrt_price() {
+    let mut pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let current = pool.slot0.sqrt_price_x96;
+
+    let amounts = assert_reaches(&pool, current - U256::from(10u128.pow(20)));
+    assert!(amounts.zero_for_one);
+    assert!(amounts.fee_amount > U256::ZERO);
+    // the fee is the pool fee on the input net of the fee
+    assert_eq!(
+        amounts.fee_amount,
+        full_math::mul_div_rounding_up(
+            amounts.amount_in - amounts.fee_amount,
+            U256::from(100),
+            U256::from(999_900)
+        )
+        .unwrap()
+    );
+    let amounts = assert_reaches(&pool, current + U256::from(10u128.pow(20)));
+    assert!(!amounts.zero_for_one);
+
+    // the pool fee is charged on the input even with the protocol fee on
+    pool.slot0.fee_protocol = 0x44;
+    assert_reaches(&pool, current + U256::from(10u128.pow(24)));
+
+    let amounts = pool.amount_to_reach_sqrt_price(current).unwrap();
+    assert_eq!(amounts.amount_in, U256::ZERO);
+    assert_eq!(amounts.amount_out, U256::ZERO);
+    assert_eq!(amounts.tick, pool.slot0.tick);
+
+    assert!(matches!(
+        pool.amount_to_reach_sqrt_price(tick_math::MIN_SQRT_RATIO),
+        Err(UniswapV3MathError::InvalidSqrtPriceLimit)
+    ));
+    assert!(matches!(
+        pool.amount_to_reach_tick(tick_math::MAX_TICK + 1),
+        Err(UniswapV3MathError::T)
+    ));
+}
@@ -0,0 +1,184 @@
+use alloy_primitives::aliases::U24;
+use alloy_primitives::{Address, I256, U256};
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::path::Path;
+use crate::pool_data::SwapResult;
+
+#[derive(Error, Debug)]
+pub enum QuoteError {
+    #[error("no pool for {token_in} -> {token_out} with fee {fee}")]
+    PoolNotFound {
+        token_in: Address,
+        token_out: Address,
+        fee: U24,
+    },
+    #[error("amount {0} does not fit in an int256")]
+    AmountTooLarge(U256),
+    #[error("pool {pool} can't provide the requested output amount")]
+    InsufficientLiquidity { pool: Address },
+    #[error("swap failed: {0}")]
+    Swap(#[from] UniswapV3MathError),
+}
+
+/// One swap of a path quote.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopQuote {
+    pub pool: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub fee: U24,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub sqrt_price_x96_after: U256,
+    /// The initialized ticks crossed, in the order they were crossed.
+    pub ticks_crossed: Vec<i32>,
+}
+
+/// Result of quoting a path. `amount` is the output of an exact input quote and the input of
+/// an exact output quote.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathQuote {
+    pub amount: U256,
+    /// The hops in path order, i.e. from the output token for exact output quotes.
+    pub hops: Vec<HopQuote>,
+}
+
+/// Quotes paths across a set of pools, like the periphery's QuoterV2. Each hop is quoted against
+/// the current state of its pool, without the effects of earlier hops on the same pool.
+#[derive(Clone, Debug, Default)]
+pub struct Quoter<'a> {
+    pools: HashMap<(Address, Address, U24), &'a UniswapV3Pool>,
+}
+
+impl<'a> Quoter<'a> {
+    pub fn new(pools: impl IntoIterator<Item = &'a UniswapV3Pool>) -> Self {
+        Self {
+            pools: pools
+                .into_iter()
+                .map(|pool| ((pool.token0, pool.token1, pool.fee), pool))
+                .collect(),
+        }
+    }
+
+    /// The pool between the two tokens with the given fee.
+    pub fn pool(&self, token_a: Address, token_b: Address, fee: U24) -> Option<&'a UniswapV3Pool> {
+        let (token0, token1) = if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+        self.pools.get(&(token0, token1, fee)).copied()
+    }
+
+    pub fn quote_exact_input(&self, path: &Path, amount_in: U256) -> Result<PathQuote, QuoteError> {
+        let mut amount = amount_in;
+        let mut hops = Vec::new();
+
+        for (token_in, fee, token_out) in path.hops() {
+            let hop = self.quote_exact_input_single(token_in, token_out, fee, amount)?;
+            amount = hop.amount_out;
+            hops.push(hop);
+        }
+
+        Ok(PathQuote { amount, hops })
+    }
+
+    /// Quotes `path`, listed from the output token to the input token, for exactly `amount_out`
+    /// of the output token.
+    pub fn quote_exact_output(
+        &self,
+        path: &Path,
+        amount_out: U256,
+    ) -> Result<PathQuote, QuoteError> {
+        let mut amount = amount_out;
+        let mut hops = Vec::new();
+
+        for (token_out, fee, token_in) in path.hops() {
+            let hop = self.quote_exact_output_single(token_in, token_out, fee, amount)?;
+            amount = hop.amount_in;
+            hops.push(hop);
+        }
+
+        Ok(PathQuote { amount, hops })
+    }
+
+    pub fn quote_exact_input_single(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: U24,
+        amount_in: U256,
+    ) -> Result<HopQuote, QuoteError> {
+        let pool = self.find(token_in, token_out, fee)?;
+        let amount_in =
+            I256::try_from(amount_in).map_err(|_| QuoteError::AmountTooLarge(amount_in))?;
+        let result = pool.quote_exact_input(token_in < token_out, amount_in)?;
+        Ok(hop_quote(pool, token_in, token_out, &result))
+    }
+
+    /// Fails if the pool runs out of liquidity before `amount_out` is reached.
+    pub fn quote_exact_output_single(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: U24,
+        amount_out: U256,
+    ) -> Result<HopQuote, QuoteError> {
+        let pool = self.find(token_in, token_out, fee)?;
+        let requested =
+            I256::try_from(amount_out).map_err(|_| QuoteError::AmountTooLarge(amount_out))?;
+        let result = pool.quote_exact_output(token_in < token_out, requested)?;
+        let hop = hop_quote(pool, token_in, token_out, &result);
+        if hop.amount_out != amount_out {
+            return Err(QuoteError::InsufficientLiquidity {
+                pool: pool.metadata.address,
+            });
+        }
+        Ok(hop)
+    }
+
+    fn find(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        fee: U24,
+    ) -> Result<&'a UniswapV3Pool, QuoteError> {
+        self.pool(token_in, token_out, fee)
+            .ok_or(QuoteError::PoolNotFound {
+                token_in,
+                token_out,
+                fee,
+            })
+    }
+}
+
+pub(crate) fn hop_quote(
+    pool: &UniswapV3Pool,
+    token_in: Address,
+    token_out: Address,
+    result: &SwapResult,
+) -> HopQuote {
+    let (amount_in, amount_out) = if result.zero_for_one {
+        (result.amount0, -result.amount1)
+    } else {
+        (result.amount1, -result.amount0)
+    };
+    HopQuote {
+        pool: pool.metadata.address,
+        token_in,
+        token_out,
+        fee: pool.fee,
+        amount_in: amount_in.into_raw(),
+        amount_out: amount_out.into_raw(),
+        sqrt_price_x96_after: result.sqrt_price_x96,
+        ticks_crossed: result
+            .ticks_crossed
+            .iter()
+            .map(|crossing| crossing.tick)
+            .collect(),
+    }
+}
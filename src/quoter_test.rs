@@ -0,0 +1,129 @@
+use super::*;
+use crate::path::{Path, PathError};
+use crate::quoter::{QuoteError, Quoter};
+use crate::test_utils::{SNAPSHOT, address};
+
+/// The snapshot pool, and a copy of it between its token1 and a third token.
+fn pools() -> (UniswapV3Pool, UniswapV3Pool) {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let mut second = pool.clone();
+    second.metadata.address = address("0x0000000000000000000000000000000000000b0b");
+    second.token0 = pool.token1;
+    second.token1 = address("0xffffffffffffffffffffffffffffffffffffffff");
+    second.fee = U24::from(500);
+    (pool, second)
+}
+
+#[test]
+fn test_path_encoding() {
+    let (pool, second) = pools();
+    let path = Path::new(
+        vec![pool.token0, pool.token1, second.token1],
+        vec![pool.fee, second.fee],
+    )
+    .unwrap();
+
+    let encoded = path.encode();
+    assert_eq!(encoded.len(), 66);
+    assert_eq!(&encoded[..20], pool.token0.as_slice());
+    assert_eq!(&encoded[20..23], &[0x00, 0x00, 0x64]);
+    assert_eq!(&encoded[43..46], &[0x00, 0x01, 0xf4]);
+    assert_eq!(Path::decode(&encoded).unwrap(), path);
+    assert_eq!(path.reversed().reversed(), path);
+    assert_eq!(path.reversed().tokens()[0], second.token1);
+
+    assert_eq!(Path::decode(&encoded[..65]), Err(PathError::Length(65)));
+    assert_eq!(Path::decode(&encoded[..20]), Err(PathError::Length(20)));
+    assert_eq!(
+        Path::new(vec![pool.token0], vec![pool.fee]),
+        Err(PathError::Hops { tokens: 1, fees: 1 })
+    );
+}
+
+#[test]
+fn test_quote_exact_input() {
+    let (pool, second) = pools();
+    let quoter = Quoter::new([&pool, &second]);
+    let path = Path::new(
+        vec![pool.token0, pool.token1, second.token1],
+        vec![pool.fee, second.fee],
+    )
+    .unwrap();
+    let amount_in = U256::from(10u128.pow(23));
+
+    let quote = quoter.quote_exact_input(&path, amount_in).unwrap();
+    assert_eq!(quote.hops.len(), 2);
+
+    // each hop is the single pool quote of the previous output
+    let first = pool
+        .quote_exact_input(true, I256::try_from(amount_in).unwrap())
+        .unwrap();
+    assert_eq!(quote.hops[0].amount_in, amount_in);
+    assert_eq!(quote.hops[0].amount_out, (-first.amount1).into_raw());
+    assert_eq!(quote.hops[0].sqrt_price_x96_after, first.sqrt_price_x96);
+    assert_eq!(quote.hops[0].pool, pool.metadata.address);
+    let second_result = second
+        .quote_exact_input(true, first.amount1.wrapping_neg())
+        .unwrap();
+    assert_eq!(quote.hops[1].amount_in, quote.hops[0].amount_out);
+    assert_eq!(quote.amount, (-second_result.amount1).into_raw());
+    assert_eq!(quote.hops[1].token_out, second.token1);
+    assert!(
+        quote
+            .hops
+            .iter()
+            .all(|hop| hop.ticks_crossed.windows(2).all(|t| t[0] > t[1]))
+    );
+}
+
+#[test]
+fn test_quote_exact_output() {
+    let (pool, second) = pools();
+    let quoter = Quoter::new([&pool, &second]);
+    let path = Path::new(
+        vec![second.token1, pool.token1, pool.token0],
+        vec![second.fee, pool.fee],
+    )
+    .unwrap();
+    let amount_out = U256::from(10u128.pow(21));
+
+    let quote = quoter.quote_exact_output(&path, amount_out).unwrap();
+    assert_eq!(quote.hops[0].token_out, second.token1);
+    assert_eq!(quote.hops[0].amount_out, amount_out);
+    assert_eq!(quote.hops[1].amount_out, quote.hops[0].amount_in);
+    assert_eq!(quote.amount, quote.hops[1].amount_in);
+
+    // paying the quoted input forward returns at least the requested output
+    let forward = quoter
+        .quote_exact_input(&path.reversed(), quote.amount)
+        .unwrap();
+    assert!(forward.amount >= amount_out);
+    assert!(forward.amount < amount_out + U256::from(10u128.pow(6)));
+}
+
+#[test]
+fn test_quote_errors() {
+    let (pool, second) = pools();
+    let quoter = Quoter::new([&pool]);
+    let path = Path::new(
+        vec![pool.token0, pool.token1, second.token1],
+        vec![pool.fee, second.fee],
+    )
+    .unwrap();
+
+    let result = quoter.quote_exact_input(&path, U256::from(1000));
+    assert!(matches!(
+        result,
+        Err(QuoteError::PoolNotFound { token_out, .. }) if token_out == second.token1
+    ));
+
+    let path = Path::new(vec![pool.token1, pool.token0], vec![pool.fee]).unwrap();
+    let result = quoter.quote_exact_output(&path, U256::from(10u128.pow(30)));
+    assert!(matches!(
+        result,
+        Err(QuoteError::InsufficientLiquidity { pool: address }) if address == pool.metadata.address
+    ));
+
+    let result = quoter.quote_exact_input(&path, U256::MAX);
+    assert!(matches!(result, Err(QuoteError::AmountTooLarge(_))));
+}
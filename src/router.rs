@@ -0,0 +1,230 @@
+use alloy_primitives::{Address, I256, U256};
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::libraries::error::UniswapV3MathError;
+use crate::libraries::full_math::mul_div;
+use crate::libraries::sqrt_price_math::Q96;
+use crate::pool_data::{SwapParams, SwapResult};
+
+#[derive(Error, Debug)]
+pub enum RouteError {
+    #[error("no pools to route through")]
+    NoPools,
+    #[error("pool {pool} trades a different pair than the first pool")]
+    PairMismatch { pool: Address },
+    #[error("amount {0} does not fit in an int256")]
+    AmountTooLarge(U256),
+    #[error("swap failed: {0}")]
+    Swap(#[from] UniswapV3MathError),
+}
+
+/// The part of a split swap going through one pool.
+#[derive(Clone, Debug)]
+pub struct SplitLeg {
+    pub pool: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// The swap simulated for the leg, `None` if the pool gets no input. It can be committed
+    /// with `UniswapV3Pool::apply` as long as the pool hasn't changed since.
+    pub result: Option<SwapResult>,
+}
+
+/// An exact input swap split across pools of the same pair.
+#[derive(Clone, Debug)]
+pub struct SplitRoute {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    /// One leg per pool, in the order the pools were given.
+    pub legs: Vec<SplitLeg>,
+}
+
+/// Splits `amount_in` across `pools`, which must all trade the same pair, to maximize the
+/// output.
+///
+/// Input goes to the pools with the best marginal price after fees until their marginal prices
+/// are equal, which is found by bisecting over a common marginal price. Each pool's input at a
+/// given price is simulated over its initialized ticks, so the split follows the step-wise
+/// liquidity of the pools rather than a constant product approximation. The legs are then
+/// simulated with `swap` on copies of the pools and the route is built from those swaps. If
+/// the pools can't absorb all of `amount_in`, each leg takes as much as its pool can.
+pub fn split_exact_input(
+    pools: &[&UniswapV3Pool],
+    zero_for_one: bool,
+    amount_in: U256,
+) -> Result<SplitRoute, RouteError> {
+    let first = pools.first().ok_or(RouteError::NoPools)?;
+    if let Some(pool) = pools
+        .iter()
+        .find(|pool| pool.token0 != first.token0 || pool.token1 != first.token1)
+    {
+        return Err(RouteError::PairMismatch {
+            pool: pool.metadata.address,
+        });
+    }
+    let amount = I256::try_from(amount_in).map_err(|_| RouteError::AmountTooLarge(amount_in))?;
+
+    let allocations = if amount == I256::ZERO {
+        vec![U256::ZERO; pools.len()]
+    } else {
+        allocate(pools, zero_for_one, amount)?
+    };
+
+    let mut legs = Vec::with_capacity(pools.len());
+    for (pool, allocation) in pools.iter().zip(allocations) {
+        if allocation == U256::ZERO {
+            legs.push(SplitLeg {
+                pool: pool.metadata.address,
+                amount_in: U256::ZERO,
+                amount_out: U256::ZERO,
+                result: None,
+            });
+            continue;
+        }
+
+        let mut simulated = (*pool).clone();
+        let result = simulated.swap(SwapParams::exact_input(
+            Address::ZERO,
+            zero_for_one,
+            I256::from_raw(allocation),
+        ))?;
+        let (amount_in, amount_out) = if zero_for_one {
+            (result.amount0, -result.amount1)
+        } else {
+            (result.amount1, -result.amount0)
+        };
+        legs.push(SplitLeg {
+            pool: pool.metadata.address,
+            amount_in: amount_in.into_raw(),
+            amount_out: amount_out.into_raw(),
+            result: Some(result),
+        });
+    }
+
+    Ok(SplitRoute {
+        amount_in: legs.iter().map(|leg| leg.amount_in).sum(),
+        amount_out: legs.iter().map(|leg| leg.amount_out).sum(),
+        legs,
+    })
+}
+
+/// The input of each pool at the marginal price where the pools absorb `amount` together.
+fn allocate(
+    pools: &[&UniswapV3Pool],
+    zero_for_one: bool,
+    amount: I256,
+) -> Result<Vec<U256>, RouteError> {
+    let fee_factors: Vec<U256> = pools.iter().map(|pool| fee_factor(pool)).collect();
+    let total = amount.into_raw();
+
+    // marginal prices are the sqrt prices scaled by the fee, token1 per token0 for zero for one
+    // swaps, which lower the price, and token0 per token1 otherwise
+    let marginal_prices = pools
+        .iter()
+        .zip(&fee_factors)
+        .map(|(pool, &factor)| marginal_price(pool.slot0.sqrt_price_x96, factor, zero_for_one))
+        .collect::<Result<Vec<_>, _>>()?;
+    // at the extreme prices every pool swaps to the end of its range
+    let (mut enough, mut short) = if zero_for_one {
+        (U256::ZERO, marginal_prices.into_iter().max().unwrap())
+    } else {
+        (U256::MAX, marginal_prices.into_iter().min().unwrap())
+    };
+
+    let inputs_at = |price: U256| -> Result<Vec<U256>, RouteError> {
+        pools
+            .iter()
+            .zip(&fee_factors)
+            .map(|(pool, &factor)| input_to_price(pool, factor, zero_for_one, amount, price))
+            .collect()
+    };
+
+    let at_limit = inputs_at(enough)?;
+    if at_limit.iter().sum::<U256>() < total {
+        return Ok(at_limit);
+    }
+
+    // keep `enough` absorbing the whole amount and `short` absorbing less
+    let mut inputs_short = inputs_at(short)?;
+    while enough.abs_diff(short) > U256::from(1) {
+        let middle = enough.min(short) + (enough.abs_diff(short) >> 1);
+        let inputs = inputs_at(middle)?;
+        if inputs.iter().sum::<U256>() >= total {
+            enough = middle;
+        } else {
+            short = middle;
+            inputs_short = inputs;
+        }
+    }
+
+    // the pools can't be split any finer than between two neighbouring prices, so the rest goes
+    // to the pools that take more at the `enough` price
+    let inputs_enough = inputs_at(enough)?;
+    let mut remaining = total - inputs_short.iter().sum::<U256>();
+    for (input, upper) in inputs_short.iter_mut().zip(inputs_enough) {
+        let extra = remaining.min(upper - *input);
+        *input += extra;
+        remaining -= extra;
+    }
+
+    Ok(inputs_short)
+}
+
+/// The input `pool` takes to reach `price`, capped at `amount`.
+fn input_to_price(
+    pool: &UniswapV3Pool,
+    fee_factor: U256,
+    zero_for_one: bool,
+    amount: I256,
+    price: U256,
+) -> Result<U256, RouteError> {
+    let current = pool.slot0.sqrt_price_x96;
+    let limit = price_limit(price, fee_factor, zero_for_one)?;
+    if (zero_for_one && limit >= current) || (!zero_for_one && limit <= current) {
+        return Ok(U256::ZERO);
+    }
+
+    let mut params = SwapParams::exact_input(Address::ZERO, zero_for_one, amount);
+    params.sqrt_price_limit_x96 = limit;
+    let result = pool.quote(&params)?;
+    let input = if zero_for_one {
+        result.amount0
+    } else {
+        result.amount1
+    };
+    Ok(input.into_raw())
+}
+
+/// The sqrt price at which a pool reaches the marginal `price`, within the limits of the pool.
+fn price_limit(
+    price: U256,
+    fee_factor: U256,
+    zero_for_one: bool,
+) -> Result<U256, UniswapV3MathError> {
+    Ok(if zero_for_one {
+        mul_div(price, Q96, fee_factor)?.max(SwapParams::no_sqrt_price_limit(true))
+    } else {
+        mul_div(price, fee_factor, Q96)?.min(SwapParams::no_sqrt_price_limit(false))
+    })
+}
+
+/// The sqrt price scaled so that pools with different fees can be compared.
+fn marginal_price(
+    sqrt_price_x96: U256,
+    fee_factor: U256,
+    zero_for_one: bool,
+) -> Result<U256, UniswapV3MathError> {
+    if zero_for_one {
+        mul_div(sqrt_price_x96, fee_factor, Q96)
+    } else {
+        mul_div(sqrt_price_x96, Q96, fee_factor)
+    }
+}
+
+/// `sqrt(1 - fee)` as a Q64.96.
+pub(crate) fn fee_factor(pool: &UniswapV3Pool) -> U256 {
+    let fee = U256::from(pool.fee);
+    let denominator = U256::from(1_000_000);
+    let scaled: U256 = ((denominator - fee) << 192) / denominator;
+    scaled.root(2)
+}
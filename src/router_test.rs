@@ -0,0 +1,147 @@
+use super::*;
+use crate::router::{RouteError, SplitRoute, split_exact_input};
+use crate::test_utils::{SNAPSHOT, address};
+
+/// The snapshot pool and copies of it at the given fees.
+fn pools(fees: &[u32]) -> Vec<UniswapV3Pool> {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let mut pools = vec![pool.clone()];
+    for (i, &fee) in fees.iter().enumerate() {
+        let mut copy = pool.clone();
+        copy.metadata.address = Address::with_last_byte(i as u8 + 1);
+        copy.fee = U24::from(fee);
+        pools.push(copy);
+    }
+    pools
+}
+
+fn output(pool: &UniswapV3Pool, zero_for_one: bool, amount_in: U256) -> U256 {
+    if amount_in == U256::ZERO {
+        return U256::ZERO;
+    }
+    let result = pool
+        .quote_exact_input(zero_for_one, I256::from_raw(amount_in))
+        .unwrap();
+    if zero_for_one {
+        (-result.amount1).into_raw()
+    } else {
+        (-result.amount0).into_raw()
+    }
+}
+
+fn assert_no_better_shift(pools: &[&UniswapV3Pool], route: &SplitRoute, zero_for_one: bool) {
+    // moving part of a leg's input to another pool doesn't increase the output
+    for from in 0..pools.len() {
+        let shift = route.legs[from].amount_in / U256::from(100);
+        if shift == U256::ZERO {
+            continue;
+        }
+        for to in (0..pools.len()).filter(|&to| to != from) {
+            let shifted: U256 = (0..pools.len())
+                .map(|i| {
+                    let mut amount_in = route.legs[i].amount_in;
+                    if i == from {
+                        amount_in -= shift;
+                    } else if i == to {
+                        amount_in += shift;
+                    }
+                    output(pools[i], zero_for_one, amount_in)
+                })
+                .sum();
+            assert!(shifted <= route.amount_out);
+        }
+    }
+}
+
+#[test]
+fn test_split_between_fee_tiers() {
+    let pools = pools(&[500, 2500]);
+    let pools: Vec<&UniswapV3Pool> = pools.iter().collect();
+    let amount_in = U256::from(10u128.pow(23));
+
+    let route = split_exact_input(&pools, true, amount_in).unwrap();
+    assert_eq!(route.amount_in, amount_in);
+    assert_eq!(route.legs.len(), 3);
+    assert!(route.legs[0].amount_in > route.legs[1].amount_in);
+    assert!(route.legs[1].amount_in > route.legs[2].amount_in);
+
+    // the legs are the swaps of their pools
+    for (pool, leg) in pools.iter().zip(&route.legs) {
+        assert_eq!(leg.pool, pool.metadata.address);
+        assert_eq!(leg.amount_out, output(pool, true, leg.amount_in));
+        if let Some(result) = &leg.result {
+            let mut swapped = (*pool).clone();
+            swapped.apply(result);
+            assert_eq!(swapped.slot0.sqrt_price_x96, result.sqrt_price_x96);
+        }
+    }
+    assert_eq!(
+        route.amount_out,
+        route.legs.iter().map(|leg| leg.amount_out).sum::<U256>()
+    );
+    assert!(route.amount_out > output(pools[0], true, amount_in));
+    assert_no_better_shift(&pools, &route, true);
+}
+
+#[test]
+fn test_split_one_for_zero() {
+    let pools = pools(&[100]);
+    let pools: Vec<&UniswapV3Pool> = pools.iter().collect();
+    let amount_in = U256::from(10u128.pow(24)) + U256::from(1);
+
+    let route = split_exact_input(&pools, false, amount_in).unwrap();
+    assert_eq!(route.amount_in, amount_in);
+    // identical pools take the same input, up to rounding
+    assert!(route.legs[0].amount_in.abs_diff(route.legs[1].amount_in) <= U256::from(2));
+    assert_no_better_shift(&pools, &route, false);
+}
+
+#[test]
+fn test_small_amount_takes_cheapest_pool() {
+    let pools = pools(&[500]);
+    let pools: Vec<&UniswapV3Pool> = vec![&pools[1], &pools[0]];
+    let amount_in = U256::from(10u128.pow(12));
+
+    let route = split_exact_input(&pools, true, amount_in).unwrap();
+    assert_eq!(route.legs[0].amount_in, U256::ZERO);
+    assert!(route.legs[0].result.is_none());
+    assert_eq!(route.legs[1].amount_in, amount_in);
+    assert_eq!(route.amount_out, output(pools[1], true, amount_in));
+}
+
+#[test]
+fn test_split_beyond_liquidity() {
+    let pools = pools(&[500]);
+    let pools: Vec<&UniswapV3Pool> = pools.iter().collect();
+    let amount_in = U256::from(10).pow(U256::from(50));
+
+    let route = split_exact_input(&pools, true, amount_in).unwrap();
+    assert!(route.amount_in < amount_in);
+    // each pool takes as much as it can
+    for (pool, leg) in pools.iter().zip(&route.legs) {
+        let result = pool
+            .quote_exact_input(true, I256::from_raw(amount_in))
+            .unwrap();
+        assert_eq!(leg.amount_in, result.amount0.into_raw());
+    }
+}
+
+#[test]
+fn test_route_errors() {
+    let mut pools = pools(&[500]);
+    pools[1].token1 = address("0xffffffffffffffffffffffffffffffffffffffff");
+    let refs: Vec<&UniswapV3Pool> = pools.iter().collect();
+
+    assert!(matches!(
+        split_exact_input(&refs, true, U256::from(1000)),
+        Err(RouteError::PairMismatch { pool }) if pool == pools[1].metadata.address
+    ));
+    assert!(matches!(
+        split_exact_input(&[], true, U256::from(1000)),
+        Err(RouteError::NoPools)
+    ));
+    assert!(matches!(
+        split_exact_input(&refs[..1], true, U256::MAX),
+        Err(RouteError::AmountTooLarge(_))
+    ));
+}
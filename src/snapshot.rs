@@ -0,0 +1,757 @@
+use alloy_primitives::aliases::{I24, U24};
+use alloy_primitives::ruint::{BaseConvertError, ParseError};
+use alloy_primitives::{Address, I16, U256};
+use indexmap::IndexMap;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, Expected, IntoDeserializer, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::UniswapV3Pool;
+use crate::pool_data::{Observation, PoolMetadata, ProtocolFees, Slot0, TickInfo};
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Cannot read snapshot: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid snapshot field {path}: {kind}")]
+    Field {
+        /// JSON pointer of the offending field, e.g. `/pool/store/slot0/tick`.
+        path: String,
+        kind: SnapshotErrorKind,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotErrorKind {
+    #[error("missing")]
+    Missing,
+    #[error("expected {0}")]
+    WrongType(String),
+    #[error("invalid hex value {0:?}")]
+    BadHex(String),
+    #[error("invalid decimal value {0:?}")]
+    BadDecimal(String),
+    #[error("invalid address {0:?}")]
+    BadAddress(String),
+    #[error("value {0} out of range")]
+    OutOfRange(String),
+}
+
+// the string adapters below can only raise their errors through `Error::custom`, so they tag the
+// message with the kind for `FieldError` to recover it
+const ADAPTER_ERROR_TAG: &str = "snapshot adapter error: ";
+
+impl SnapshotErrorKind {
+    /// Raises the kind from a string adapter, to be recovered by `from_adapter_message`.
+    fn raise<E: serde::de::Error>(self) -> E {
+        let (name, value) = match &self {
+            Self::BadHex(value) => ("hex", value),
+            Self::BadDecimal(value) => ("decimal", value),
+            Self::BadAddress(value) => ("address", value),
+            Self::OutOfRange(value) => ("range", value),
+            Self::Missing | Self::WrongType(_) => unreachable!("not raised by the adapters"),
+        };
+        E::custom(format_args!("{ADAPTER_ERROR_TAG}{name}:{value}"))
+    }
+
+    fn from_adapter_message(message: &str) -> Option<Self> {
+        let (name, value) = message.strip_prefix(ADAPTER_ERROR_TAG)?.split_once(':')?;
+        let value = value.to_string();
+        match name {
+            "hex" => Some(Self::BadHex(value)),
+            "decimal" => Some(Self::BadDecimal(value)),
+            "address" => Some(Self::BadAddress(value)),
+            "range" => Some(Self::OutOfRange(value)),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_path_to_error::Error<FieldError>> for SnapshotError {
+    fn from(error: serde_path_to_error::Error<FieldError>) -> Self {
+        let mut path = String::new();
+        for segment in error.path().iter() {
+            match segment {
+                serde_path_to_error::Segment::Seq { index } => path += &format!("/{index}"),
+                serde_path_to_error::Segment::Map { key } => path += &format!("/{key}"),
+                serde_path_to_error::Segment::Enum { variant } => path += &format!("/{variant}"),
+                serde_path_to_error::Segment::Unknown => path += "/?",
+            }
+        }
+
+        let kind = match error.into_inner() {
+            FieldError::Kind(kind) => kind,
+            FieldError::Missing(field) => {
+                // missing fields are reported on the enclosing object
+                path += &format!("/{field}");
+                SnapshotErrorKind::Missing
+            }
+            FieldError::Other(message) => {
+                return Self::Json(de::Error::custom(format_args!("{message} at {path}")));
+            }
+        };
+
+        Self::Field { path, kind }
+    }
+}
+
+/// Error of `ValueDeserializer`, keeping the kind serde reports an error with rather than only
+/// its message.
+#[derive(Error, Debug)]
+enum FieldError {
+    #[error("{0}")]
+    Kind(SnapshotErrorKind),
+    #[error("missing field `{0}`")]
+    Missing(&'static str),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl de::Error for FieldError {
+    fn custom<T: Display>(message: T) -> Self {
+        let message = message.to_string();
+        match SnapshotErrorKind::from_adapter_message(&message) {
+            Some(kind) => Self::Kind(kind),
+            None => Self::Other(message),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::Missing(field)
+    }
+
+    fn invalid_type(_: Unexpected, expected: &dyn Expected) -> Self {
+        Self::Kind(SnapshotErrorKind::WrongType(expected.to_string()))
+    }
+
+    fn invalid_value(unexpected: Unexpected, _: &dyn Expected) -> Self {
+        // numbers out of range for their type, i.e. map keys
+        let value = match unexpected {
+            Unexpected::Signed(value) => value.to_string(),
+            Unexpected::Unsigned(value) => value.to_string(),
+            Unexpected::Str(value) => value.to_string(),
+            other => other.to_string(),
+        };
+        Self::Kind(SnapshotErrorKind::OutOfRange(value))
+    }
+}
+
+/// Deserializes a parsed JSON document with `FieldError`s, which serde_json's own deserializers
+/// would turn into messages.
+struct ValueDeserializer(Value);
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::Number(number) => {
+                if let Some(value) = number.as_u64() {
+                    visitor.visit_u64(value)
+                } else if let Some(value) = number.as_i64() {
+                    visitor.visit_i64(value)
+                } else {
+                    visitor.visit_f64(number.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(value) => visitor.visit_string(value),
+            Value::Array(values) => {
+                let mut seq = SeqDeserializer::new(values.into_iter().map(ValueDeserializer));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(entries) => {
+                let mut map = MapDeserializer::new(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (KeyDeserializer(key), ValueDeserializer(value))),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, FieldError> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Object keys, which are strings in JSON but integers for the tick maps.
+struct KeyDeserializer(String);
+
+macro_rules! deserialize_integer_key {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+            if let Ok(value) = self.0.parse::<i64>() {
+                visitor.visit_i64(value)
+            } else if let Ok(value) = self.0.parse::<u64>() {
+                visitor.visit_u64(value)
+            } else {
+                visitor.visit_string(self.0)
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer {
+    type Error = FieldError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, FieldError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_integer_key!(
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_u8
+        deserialize_u16 deserialize_u32 deserialize_u64
+    );
+
+    serde::forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, FieldError> for KeyDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The document stored under `snapshots/<block>/<name>.json`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Snapshot {
+    pub state_block: u64,
+    pub pool: SnapshotPool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotPool {
+    pub name: String,
+    pub store: SnapshotPoolStore,
+    #[serde(with = "address")]
+    pub address: Address,
+    #[serde(with = "address")]
+    pub token0: Address,
+    #[serde(with = "address")]
+    pub token1: Address,
+    pub protocol: String,
+    pub dex: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotPoolStore {
+    pub version: String,
+    pub protocol: String,
+    #[serde(with = "hex")]
+    pub fee: U24,
+    #[serde(with = "decimal")]
+    pub tick_spacing: I24,
+    pub slot0: SnapshotSlot0,
+    #[serde(rename = "fee_growth_global_0x128", with = "hex")]
+    pub fee_growth_global0_x128: U256,
+    #[serde(rename = "fee_growth_global_1x128", with = "hex")]
+    pub fee_growth_global1_x128: U256,
+    #[serde(with = "hex")]
+    pub liquidity: u128,
+    // maps keep the order of the document so that a snapshot round-trips byte for byte
+    #[serde(default, with = "hex_values")]
+    pub tick_bitmap: IndexMap<i16, U256>,
+    #[serde(default)]
+    pub ticks: IndexMap<i32, SnapshotTick>,
+    pub protocol_fees: SnapshotProtocolFees,
+    /// Only present for pools whose oracle is tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle: Option<SnapshotOracle>,
+    /// Token balances held by the pool, used to seed a `Ledger`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balances: Option<SnapshotBalances>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotSlot0 {
+    /// A uint8 on Uniswap and a uint32 on PancakeSwap, see `FeeProtocolLayout`.
+    #[serde(with = "hex")]
+    pub fee_protocol: u32,
+    #[serde(with = "decimal")]
+    pub tick: i32,
+    #[serde(with = "hex")]
+    pub sqrt_price_x96: U256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotTick {
+    #[serde(with = "decimal")]
+    pub liquidity_net: i128,
+    #[serde(with = "hex")]
+    pub liquidity_gross: u128,
+    #[serde(rename = "fee_growth_outside_0x128", with = "hex")]
+    pub fee_growth_outside0_x128: U256,
+    #[serde(rename = "fee_growth_outside_1x128", with = "hex")]
+    pub fee_growth_outside1_x128: U256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotOracle {
+    /// Time the observations were last read or written at.
+    #[serde(with = "hex")]
+    pub block_timestamp: u32,
+    #[serde(with = "hex")]
+    pub observation_index: u16,
+    #[serde(with = "hex")]
+    pub observation_cardinality: u16,
+    #[serde(with = "hex")]
+    pub observation_cardinality_next: u16,
+    pub observations: Vec<SnapshotObservation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotObservation {
+    #[serde(with = "hex")]
+    pub block_timestamp: u32,
+    #[serde(with = "decimal")]
+    pub tick_cumulative: i64,
+    #[serde(with = "hex")]
+    pub seconds_per_liquidity_cumulative_x128: U256,
+    pub initialized: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotProtocolFees {
+    #[serde(with = "hex")]
+    pub token0: u128,
+    #[serde(with = "hex")]
+    pub token1: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SnapshotBalances {
+    #[serde(with = "hex")]
+    pub token0: U256,
+    #[serde(with = "hex")]
+    pub token1: U256,
+}
+
+impl Snapshot {
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, SnapshotError> {
+        Self::from_reader(json.as_bytes())
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, SnapshotError> {
+        // syntax errors are left to serde_json, the fields are then read with typed errors
+        let value: Value = serde_json::from_reader(reader)?;
+        Ok(serde_path_to_error::deserialize(ValueDeserializer(value))?)
+    }
+
+    /// Formats the snapshot the way the files under `snapshots/` are written.
+    pub fn to_json_string(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_json_file(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(fs::write(path, self.to_json_string()?)?)
+    }
+}
+
+impl TryFrom<&Snapshot> for UniswapV3Pool {
+    type Error = SnapshotError;
+
+    /// Fails if the oracle section is inconsistent, as the pool would index out of its
+    /// observations on the next swap.
+    fn try_from(snapshot: &Snapshot) -> Result<Self, Self::Error> {
+        let store = &snapshot.pool.store;
+        if let Some(oracle) = &store.oracle {
+            oracle.validate()?;
+        }
+        let oracle = store.oracle.clone().unwrap_or_default();
+
+        Ok(Self {
+            metadata: PoolMetadata {
+                address: snapshot.pool.address,
+                state_block: snapshot.state_block,
+                name: snapshot.pool.name.clone(),
+                dex: snapshot.pool.dex.clone(),
+                protocol: snapshot.pool.protocol.clone(),
+                version: store.version.clone(),
+                store_protocol: store.protocol.clone(),
+            },
+            token0: snapshot.pool.token0,
+            token1: snapshot.pool.token1,
+            fee: store.fee,
+            tick_spacing: store.tick_spacing,
+            slot0: Slot0 {
+                sqrt_price_x96: store.slot0.sqrt_price_x96,
+                tick: store.slot0.tick,
+                observation_index: oracle.observation_index,
+                observation_cardinality: oracle.observation_cardinality,
+                observation_cardinality_next: oracle.observation_cardinality_next,
+                fee_protocol: store.slot0.fee_protocol,
+            },
+            fee_growth_global0_x128: store.fee_growth_global0_x128,
+            fee_growth_global1_x128: store.fee_growth_global1_x128,
+            protocol_fees: ProtocolFees {
+                token0: store.protocol_fees.token0,
+                token1: store.protocol_fees.token1,
+            },
+            liquidity: store.liquidity,
+            ticks: store
+                .ticks
+                .iter()
+                .map(|(&tick, info)| {
+                    (
+                        tick,
+                        TickInfo {
+                            liquidity_gross: info.liquidity_gross,
+                            liquidity_net: info.liquidity_net,
+                            fee_growth_outside0_x128: info.fee_growth_outside0_x128,
+                            fee_growth_outside1_x128: info.fee_growth_outside1_x128,
+                        },
+                    )
+                })
+                .collect(),
+            tick_bitmap: store
+                .tick_bitmap
+                .iter()
+                .map(|(&word_pos, &word)| (I16::unchecked_from(word_pos), word))
+                .collect(),
+            positions: HashMap::new(),
+            observations: oracle
+                .observations
+                .iter()
+                .map(|observation| Observation {
+                    block_timestamp: observation.block_timestamp,
+                    tick_cumulative: observation.tick_cumulative,
+                    seconds_per_liquidity_cumulative_x128: observation
+                        .seconds_per_liquidity_cumulative_x128,
+                    initialized: observation.initialized,
+                })
+                .collect(),
+            block_timestamp: oracle.block_timestamp,
+        })
+    }
+}
+
+impl SnapshotOracle {
+    fn validate(&self) -> Result<(), SnapshotError> {
+        let out_of_range = |field: &str, value: String| SnapshotError::Field {
+            path: format!("/pool/store/oracle/{field}"),
+            kind: SnapshotErrorKind::OutOfRange(value),
+        };
+        if self.observation_index >= self.observation_cardinality {
+            return Err(out_of_range(
+                "observation_index",
+                format!("{:#x}", self.observation_index),
+            ));
+        }
+        if self.observations.len() < self.observation_cardinality_next as usize {
+            return Err(out_of_range(
+                "observations",
+                self.observations.len().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl UniswapV3Pool {
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::try_from(&Snapshot::from_json_file(path)?)
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, SnapshotError> {
+        Self::try_from(&Snapshot::from_json_str(json)?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, SnapshotError> {
+        Self::try_from(&Snapshot::from_reader(reader)?)
+    }
+
+    /// Builds the snapshot document of the current pool state, with ticks and bitmap words in
+    /// ascending order.
+    pub fn to_snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        self.write_state(&mut snapshot);
+        snapshot
+    }
+
+    /// Writes the current pool state into `snapshot`, e.g. the document the pool was loaded
+    /// from. Ticks and bitmap words already in the document keep their position and new ones are
+    /// appended in ascending order, so an unchanged pool is written back byte for byte.
+    pub fn write_state(&self, snapshot: &mut Snapshot) {
+        snapshot.state_block = self.metadata.state_block;
+        snapshot.pool.name.clone_from(&self.metadata.name);
+        snapshot.pool.address = self.metadata.address;
+        snapshot.pool.token0 = self.token0;
+        snapshot.pool.token1 = self.token1;
+        snapshot.pool.protocol.clone_from(&self.metadata.protocol);
+        snapshot.pool.dex.clone_from(&self.metadata.dex);
+
+        let store = &mut snapshot.pool.store;
+        store.version.clone_from(&self.metadata.version);
+        store.protocol.clone_from(&self.metadata.store_protocol);
+        store.fee = self.fee;
+        store.tick_spacing = self.tick_spacing;
+        store.slot0 = SnapshotSlot0 {
+            fee_protocol: self.slot0.fee_protocol,
+            tick: self.slot0.tick,
+            sqrt_price_x96: self.slot0.sqrt_price_x96,
+        };
+        store.fee_growth_global0_x128 = self.fee_growth_global0_x128;
+        store.fee_growth_global1_x128 = self.fee_growth_global1_x128;
+        store.liquidity = self.liquidity;
+        store.protocol_fees = SnapshotProtocolFees {
+            token0: self.protocol_fees.token0,
+            token1: self.protocol_fees.token1,
+        };
+        store.oracle = (self.slot0.observation_cardinality > 0).then(|| SnapshotOracle {
+            block_timestamp: self.block_timestamp,
+            observation_index: self.slot0.observation_index,
+            observation_cardinality: self.slot0.observation_cardinality,
+            observation_cardinality_next: self.slot0.observation_cardinality_next,
+            observations: self
+                .observations
+                .iter()
+                .map(|observation| SnapshotObservation {
+                    block_timestamp: observation.block_timestamp,
+                    tick_cumulative: observation.tick_cumulative,
+                    seconds_per_liquidity_cumulative_x128: observation
+                        .seconds_per_liquidity_cumulative_x128,
+                    initialized: observation.initialized,
+                })
+                .collect(),
+        });
+
+        let tick_bitmap = self
+            .tick_bitmap
+            .iter()
+            .map(|(word_pos, &word)| (word_pos.as_i16(), word))
+            .collect();
+        store.tick_bitmap = merge_ordered(&store.tick_bitmap, tick_bitmap);
+
+        let ticks = self
+            .ticks
+            .iter()
+            .map(|(&tick, info)| {
+                (
+                    tick,
+                    SnapshotTick {
+                        liquidity_net: info.liquidity_net,
+                        liquidity_gross: info.liquidity_gross,
+                        fee_growth_outside0_x128: info.fee_growth_outside0_x128,
+                        fee_growth_outside1_x128: info.fee_growth_outside1_x128,
+                    },
+                )
+            })
+            .collect();
+        store.ticks = merge_ordered(&store.ticks, ticks);
+    }
+
+    pub fn to_json_file(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        self.to_snapshot().to_json_file(path)
+    }
+}
+
+// keys of `order` that are still present come first, in their original order
+fn merge_ordered<K, V, T>(order: &IndexMap<K, T>, mut values: HashMap<K, V>) -> IndexMap<K, V>
+where
+    K: Copy + Ord + std::hash::Hash,
+{
+    let mut merged: IndexMap<K, V> = order
+        .keys()
+        .filter_map(|key| values.remove(key).map(|value| (*key, value)))
+        .collect();
+    let mut rest: Vec<_> = values.into_iter().collect();
+    rest.sort_unstable_by_key(|(key, _)| *key);
+    merged.extend(rest);
+    merged
+}
+
+trait HexValue: Sized {
+    fn to_u256(&self) -> U256;
+    fn from_u256(value: U256) -> Option<Self>;
+}
+
+impl HexValue for U256 {
+    fn to_u256(&self) -> U256 {
+        *self
+    }
+
+    fn from_u256(value: U256) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl HexValue for U24 {
+    fn to_u256(&self) -> U256 {
+        U256::from(*self)
+    }
+
+    fn from_u256(value: U256) -> Option<Self> {
+        Self::checked_from_limbs_slice(value.as_limbs())
+    }
+}
+
+macro_rules! impl_hex_value {
+    ($($t:ty),*) => {$(
+        impl HexValue for $t {
+            fn to_u256(&self) -> U256 {
+                U256::from(*self)
+            }
+
+            fn from_u256(value: U256) -> Option<Self> {
+                value.try_into().ok()
+            }
+        }
+    )*};
+}
+
+impl_hex_value!(u8, u16, u32, u128);
+
+fn parse_hex<T: HexValue, E: serde::de::Error>(value: &str) -> Result<T, E> {
+    let parsed = U256::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| match e {
+        ParseError::BaseConvertError(BaseConvertError::Overflow) => {
+            SnapshotErrorKind::OutOfRange(value.to_string()).raise()
+        }
+        _ => SnapshotErrorKind::BadHex(value.to_string()).raise(),
+    })?;
+    T::from_u256(parsed).ok_or_else(|| SnapshotErrorKind::OutOfRange(value.to_string()).raise())
+}
+
+/// Unsigned numbers as `0x`-prefixed lowercase hex strings, e.g. `"0x64"`.
+mod hex {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{HexValue, parse_hex};
+
+    pub(super) fn serialize<T: HexValue, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:#x}", value.to_u256()))
+    }
+
+    pub(super) fn deserialize<'de, T: HexValue, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        parse_hex(&String::deserialize(deserializer)?)
+    }
+}
+
+/// Maps whose values are hex strings, i.e. the tick bitmap.
+mod hex_values {
+    use alloy_primitives::U256;
+    use indexmap::IndexMap;
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::parse_hex;
+
+    pub(super) fn serialize<S: Serializer>(
+        map: &IndexMap<i16, U256>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_map(Some(map.len()))?;
+        for (key, value) in map {
+            state.serialize_entry(key, &format!("{value:#x}"))?;
+        }
+        state.end()
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<IndexMap<i16, U256>, D::Error> {
+        IndexMap::<i16, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(key, value)| Ok((key, parse_hex(&value)?)))
+            .collect()
+    }
+}
+
+/// Numbers as decimal strings, e.g. `"-887272"`, which JSON numbers can't always hold exactly.
+/// Only signed numbers that fit an `i128` are read back.
+pub(crate) mod decimal {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::num::IntErrorKind;
+
+    use super::SnapshotErrorKind;
+
+    pub(crate) fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub(super) fn deserialize<'de, T: TryFrom<i128>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let parsed = value.parse::<i128>().map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                SnapshotErrorKind::OutOfRange(value.clone()).raise()
+            }
+            _ => SnapshotErrorKind::BadDecimal(value.clone()).raise(),
+        })?;
+        T::try_from(parsed).map_err(|_| SnapshotErrorKind::OutOfRange(value).raise())
+    }
+}
+
+/// Addresses as lowercase `0x`-prefixed hex strings.
+mod address {
+    use alloy_primitives::Address;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::SnapshotErrorKind;
+
+    pub(super) fn serialize<S: Serializer>(
+        address: &Address,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{address:?}"))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Address, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value
+            .parse()
+            .map_err(|_| SnapshotErrorKind::BadAddress(value).raise())
+    }
+}
@@ -0,0 +1,121 @@
+use alloy_primitives::Address;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::UniswapV3Pool;
+use crate::snapshot::SnapshotError;
+
+/// Index of a snapshot tree laid out as `<root>/<block>/<Dex>.<Ver>.<Sym0>.<Sym1>.<address>.json`.
+///
+/// Only paths are indexed, pools are loaded when asked for. Entries that don't follow the layout
+/// are skipped.
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotStore {
+    pools: BTreeMap<Address, BTreeMap<u64, PathBuf>>,
+}
+
+impl SnapshotStore {
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let mut pools: BTreeMap<Address, BTreeMap<u64, PathBuf>> = BTreeMap::new();
+
+        for dir in fs::read_dir(root)? {
+            let dir = dir?;
+            let Some(block) = dir
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+
+            for file in fs::read_dir(dir.path())? {
+                let path = file?.path();
+                if let Some(address) = address_from_file_name(&path) {
+                    pools.entry(address).or_default().insert(block, path);
+                }
+            }
+        }
+
+        Ok(Self { pools })
+    }
+
+    /// Addresses of all indexed pools, in ascending order.
+    pub fn addresses(&self) -> impl Iterator<Item = Address> + '_ {
+        self.pools.keys().copied()
+    }
+
+    /// Blocks with a snapshot of `address`, in ascending order.
+    pub fn blocks(&self, address: Address) -> impl Iterator<Item = u64> + '_ {
+        self.pools
+            .get(&address)
+            .into_iter()
+            .flat_map(|blocks| blocks.keys().copied())
+    }
+
+    /// All blocks with at least one snapshot, in ascending order.
+    pub fn all_blocks(&self) -> Vec<u64> {
+        let mut blocks: Vec<u64> = self
+            .pools
+            .values()
+            .flat_map(|blocks| blocks.keys().copied())
+            .collect();
+        blocks.sort_unstable();
+        blocks.dedup();
+        blocks
+    }
+
+    pub fn path(&self, address: Address, block: u64) -> Option<&Path> {
+        self.pools.get(&address)?.get(&block).map(PathBuf::as_path)
+    }
+
+    /// The latest snapshot of `address` taken at or before `block`, with its block.
+    pub fn latest_path(&self, address: Address, block: u64) -> Option<(u64, &Path)> {
+        self.pools
+            .get(&address)?
+            .range(..=block)
+            .next_back()
+            .map(|(&block, path)| (block, path.as_path()))
+    }
+
+    pub fn load(
+        &self,
+        address: Address,
+        block: u64,
+    ) -> Option<Result<UniswapV3Pool, SnapshotError>> {
+        self.path(address, block).map(UniswapV3Pool::from_json_file)
+    }
+
+    /// Loads the latest snapshot of `address` taken at or before `block`.
+    pub fn load_latest(
+        &self,
+        address: Address,
+        block: u64,
+    ) -> Option<Result<UniswapV3Pool, SnapshotError>> {
+        self.latest_path(address, block)
+            .map(|(_, path)| UniswapV3Pool::from_json_file(path))
+    }
+
+    /// Lazily loads every pool with a snapshot at exactly `block`, in address order.
+    pub fn load_block(
+        &self,
+        block: u64,
+    ) -> impl Iterator<Item = Result<UniswapV3Pool, SnapshotError>> + '_ {
+        self.pools
+            .values()
+            .filter_map(move |blocks| blocks.get(&block))
+            .map(UniswapV3Pool::from_json_file)
+    }
+}
+
+// the address is the last dot separated part of the file name
+fn address_from_file_name(path: &Path) -> Option<Address> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    stem.rsplit('.').next()?.parse().ok()
+}
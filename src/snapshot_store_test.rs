@@ -0,0 +1,106 @@
+use super::*;
+use snapshot::SnapshotError;
+use snapshot_store::SnapshotStore;
+use std::fs;
+use std::str::FromStr;
+
+fn pool_address() -> Address {
+    Address::from_str("0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76").unwrap()
+}
+
+#[test]
+fn test_index() {
+    let store = SnapshotStore::open("snapshots").unwrap();
+
+    assert_eq!(store.addresses().collect::<Vec<_>>(), vec![pool_address()]);
+    assert_eq!(
+        store.blocks(pool_address()).collect::<Vec<_>>(),
+        vec![54994241, 55002250]
+    );
+    assert_eq!(store.all_blocks(), vec![54994241, 55002250]);
+    assert_eq!(store.blocks(Address::ZERO).count(), 0);
+    assert!(store.path(pool_address(), 54994241).is_some());
+    assert!(store.path(pool_address(), 54994242).is_none());
+}
+
+#[test]
+fn test_latest_at_or_before() {
+    let store = SnapshotStore::open("snapshots").unwrap();
+
+    //before the first snapshot
+    assert!(store.latest_path(pool_address(), 54994240).is_none());
+    assert!(store.load_latest(pool_address(), 54994240).is_none());
+
+    //at a snapshot
+    let (block, _) = store.latest_path(pool_address(), 54994241).unwrap();
+    assert_eq!(block, 54994241);
+
+    //between snapshots
+    let (block, _) = store.latest_path(pool_address(), 55002249).unwrap();
+    assert_eq!(block, 54994241);
+    let pool = store
+        .load_latest(pool_address(), 55002249)
+        .unwrap()
+        .unwrap();
+    assert_eq!(pool.slot0.tick, 20603);
+
+    //after the last snapshot
+    let pool = store
+        .load_latest(pool_address(), u64::MAX)
+        .unwrap()
+        .unwrap();
+    assert_eq!(pool.slot0.tick, 20388);
+
+    //unknown pool
+    assert!(store.load_latest(Address::ZERO, u64::MAX).is_none());
+}
+
+#[test]
+fn test_load_block() {
+    let store = SnapshotStore::open("snapshots").unwrap();
+
+    let pools: Vec<_> = store
+        .load_block(55002250)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(pools.len(), 1);
+    assert_eq!(pools[0].metadata.address, pool_address());
+    assert_eq!(pools[0].slot0.tick, 20388);
+
+    let pool = store.load(pool_address(), 54994241).unwrap().unwrap();
+    assert_eq!(pool.slot0.tick, 20603);
+
+    assert_eq!(store.load_block(55000000).count(), 0);
+}
+
+#[test]
+fn test_skips_entries_outside_the_layout() {
+    let root = std::env::temp_dir().join("uniswap_v3_snapshot_store_layout");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("100")).unwrap();
+    fs::create_dir_all(root.join("latest")).unwrap();
+    fs::write(root.join("100/README.md"), "").unwrap();
+    fs::write(root.join("100/Pan.V3.A.B.not-an-address.json"), "").unwrap();
+    fs::write(
+        root.join("latest/Pan.V3.A.B.0x0000000000000000000000000000000000000001.json"),
+        "",
+    )
+    .unwrap();
+    fs::write(root.join("200"), "").unwrap();
+    fs::copy(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+        root.join("100/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json"),
+    )
+    .unwrap();
+
+    let store = SnapshotStore::open(&root).unwrap();
+    assert_eq!(store.addresses().collect::<Vec<_>>(), vec![pool_address()]);
+    assert_eq!(store.all_blocks(), vec![100]);
+
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(matches!(
+        SnapshotStore::open(&root),
+        Err(SnapshotError::Io(_))
+    ));
+}
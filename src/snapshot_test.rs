@@ -0,0 +1,301 @@
+use super::*;
+use crate::test_utils::{SNAPSHOT, SNAPSHOT_2};
+use serde_json::Value;
+use snapshot::{Snapshot, SnapshotError, SnapshotErrorKind, SnapshotObservation, SnapshotOracle};
+use std::fs;
+use std::str::FromStr;
+
+fn load_with(path: &str, value: Value) -> Result<UniswapV3Pool, SnapshotError> {
+    let mut json: Value = serde_json::from_str(&fs::read_to_string(SNAPSHOT).unwrap()).unwrap();
+    *json.pointer_mut(path).unwrap() = value;
+    UniswapV3Pool::from_json_str(&json.to_string())
+}
+
+fn load_without(parent: &str, key: &str) -> Result<UniswapV3Pool, SnapshotError> {
+    let mut json: Value = serde_json::from_str(&fs::read_to_string(SNAPSHOT).unwrap()).unwrap();
+    json.pointer_mut(parent)
+        .unwrap()
+        .as_object_mut()
+        .unwrap()
+        .remove(key);
+    UniswapV3Pool::from_json_str(&json.to_string())
+}
+
+fn field_error(result: Result<UniswapV3Pool, SnapshotError>) -> (String, SnapshotErrorKind) {
+    match result {
+        Err(SnapshotError::Field { path, kind }) => (path, kind),
+        other => panic!("expected a field error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_from_json_str_and_reader() {
+    let from_file = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    let json = fs::read_to_string(SNAPSHOT).unwrap();
+    let from_str = UniswapV3Pool::from_json_str(&json).unwrap();
+    let from_reader = UniswapV3Pool::from_reader(json.as_bytes()).unwrap();
+
+    for pool in [&from_str, &from_reader] {
+        assert_eq!(pool.token0, from_file.token0);
+        assert_eq!(pool.slot0.sqrt_price_x96, from_file.slot0.sqrt_price_x96);
+        assert_eq!(pool.liquidity, from_file.liquidity);
+        assert_eq!(pool.ticks.len(), from_file.ticks.len());
+        assert_eq!(pool.tick_bitmap, from_file.tick_bitmap);
+    }
+
+    assert_eq!(from_file.fee, U24::from(100));
+    assert_eq!(from_file.tick_spacing, I24::ONE);
+    assert_eq!(from_file.slot0.tick, 20603);
+    assert_eq!(from_file.ticks.len(), 49);
+    assert_eq!(
+        from_file.ticks[&30577].liquidity_net,
+        -883708840929964143867923
+    );
+    assert_eq!(
+        from_file.tick_bitmap[&I16::unchecked_from(-3466)],
+        U256::from(0x1000000)
+    );
+}
+
+#[test]
+fn test_metadata() {
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT).unwrap();
+    assert_eq!(
+        pool.metadata,
+        PoolMetadata {
+            address: Address::from_str("0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76").unwrap(),
+            state_block: 54994241,
+            name: "Pan.V3.USD1.MERL".to_string(),
+            dex: "Pancake".to_string(),
+            protocol: "V3".to_string(),
+            version: "v3".to_string(),
+            store_protocol: "V3Pool".to_string(),
+        }
+    );
+    assert_eq!(
+        pool.metadata.to_string(),
+        "Pan.V3.USD1.MERL 0xb604d4e46509fe1c1ef70ab4a4941d12a49dbd76 @ 54994241"
+    );
+
+    let pool = UniswapV3Pool::from_json_file(SNAPSHOT_2).unwrap();
+    assert_eq!(pool.metadata.state_block, 55002249);
+}
+
+#[test]
+fn test_missing_field() {
+    let (path, kind) = field_error(load_without("/pool/store/slot0", "tick"));
+    assert_eq!(path, "/pool/store/slot0/tick");
+    assert_eq!(kind, SnapshotErrorKind::Missing);
+
+    let (path, kind) = field_error(load_without("/pool/store/ticks/22082", "liquidity_net"));
+    assert_eq!(path, "/pool/store/ticks/22082/liquidity_net");
+    assert_eq!(kind, SnapshotErrorKind::Missing);
+
+    // pools without initialized ticks may omit the maps entirely
+    let pool = load_without("/pool/store", "ticks").unwrap();
+    assert!(pool.ticks.is_empty());
+}
+
+#[test]
+fn test_wrong_type() {
+    let (path, kind) = field_error(load_with("/pool/store/slot0/tick", Value::from(20603)));
+    assert_eq!(path, "/pool/store/slot0/tick");
+    assert_eq!(kind, SnapshotErrorKind::WrongType("a string".to_string()));
+
+    let (path, kind) = field_error(load_with("/pool/store/tick_bitmap", Value::from("0x0")));
+    assert_eq!(path, "/pool/store/tick_bitmap");
+    assert_eq!(kind, SnapshotErrorKind::WrongType("a map".to_string()));
+}
+
+#[test]
+fn test_bad_values() {
+    let (path, kind) = field_error(load_with("/pool/store/liquidity", Value::from("0xzz")));
+    assert_eq!(path, "/pool/store/liquidity");
+    assert_eq!(kind, SnapshotErrorKind::BadHex("0xzz".to_string()));
+
+    let (path, kind) = field_error(load_with(
+        "/pool/store/ticks/22082/liquidity_net",
+        Value::from("12ab"),
+    ));
+    assert_eq!(path, "/pool/store/ticks/22082/liquidity_net");
+    assert_eq!(kind, SnapshotErrorKind::BadDecimal("12ab".to_string()));
+
+    // the value is reported verbatim, even when it reads like an error message
+    let value = "0xzz, expected a map";
+    let (_, kind) = field_error(load_with("/pool/store/liquidity", Value::from(value)));
+    assert_eq!(kind, SnapshotErrorKind::BadHex(value.to_string()));
+
+    let (path, kind) = field_error(load_with("/pool/token0", Value::from("0x1234")));
+    assert_eq!(path, "/pool/token0");
+    assert_eq!(kind, SnapshotErrorKind::BadAddress("0x1234".to_string()));
+}
+
+#[test]
+fn test_out_of_range() {
+    let (path, kind) = field_error(load_with(
+        "/pool/store/slot0/fee_protocol",
+        Value::from("0x100000000"),
+    ));
+    assert_eq!(path, "/pool/store/slot0/fee_protocol");
+    assert_eq!(
+        kind,
+        SnapshotErrorKind::OutOfRange("0x100000000".to_string())
+    );
+
+    let (path, kind) = field_error(load_with(
+        "/pool/store/liquidity",
+        Value::from(format!("0x1{}", "0".repeat(32))),
+    ));
+    assert_eq!(path, "/pool/store/liquidity");
+    assert!(matches!(kind, SnapshotErrorKind::OutOfRange(_)));
+
+    let (path, kind) = field_error(load_with(
+        "/pool/store/slot0/sqrt_price_x96",
+        Value::from(format!("0x1{}", "0".repeat(64))),
+    ));
+    assert_eq!(path, "/pool/store/slot0/sqrt_price_x96");
+    assert!(matches!(kind, SnapshotErrorKind::OutOfRange(_)));
+
+    // bitmap words are indexed by an int16
+    let mut json: Value = serde_json::from_str(&fs::read_to_string(SNAPSHOT).unwrap()).unwrap();
+    let bitmap = json.pointer_mut("/pool/store/tick_bitmap").unwrap();
+    bitmap
+        .as_object_mut()
+        .unwrap()
+        .insert("40000".to_string(), Value::from("0x1"));
+    let (path, kind) = field_error(UniswapV3Pool::from_json_str(&json.to_string()));
+    assert_eq!(path, "/pool/store/tick_bitmap/40000");
+    assert_eq!(kind, SnapshotErrorKind::OutOfRange("40000".to_string()));
+}
+
+#[test]
+fn test_unreadable_snapshot() {
+    assert!(matches!(
+        UniswapV3Pool::from_json_file("snapshots/0/missing.json"),
+        Err(SnapshotError::Io(_))
+    ));
+    assert!(matches!(
+        UniswapV3Pool::from_json_str("{\"pool\":"),
+        Err(SnapshotError::Json(_))
+    ));
+}
+
+#[test]
+fn test_inconsistent_oracle() {
+    let mut snapshot = Snapshot::from_json_file(SNAPSHOT).unwrap();
+    let observation = SnapshotObservation {
+        block_timestamp: 1,
+        initialized: true,
+        ..Default::default()
+    };
+    snapshot.pool.store.oracle = Some(SnapshotOracle {
+        block_timestamp: 1,
+        observation_index: 1,
+        observation_cardinality: 2,
+        observation_cardinality_next: 3,
+        observations: vec![observation.clone(); 3],
+    });
+    let pool = UniswapV3Pool::try_from(&snapshot).unwrap();
+    assert_eq!(pool.observations.len(), 3);
+
+    let oracle = snapshot.pool.store.oracle.as_mut().unwrap();
+    oracle.observation_index = 2;
+    let (path, kind) = field_error(UniswapV3Pool::try_from(&snapshot));
+    assert_eq!(path, "/pool/store/oracle/observation_index");
+    assert_eq!(kind, SnapshotErrorKind::OutOfRange("0x2".to_string()));
+
+    let oracle = snapshot.pool.store.oracle.as_mut().unwrap();
+    oracle.observation_index = 1;
+    oracle.observations.pop();
+    let (path, kind) = field_error(UniswapV3Pool::try_from(&snapshot));
+    assert_eq!(path, "/pool/store/oracle/observations");
+    assert_eq!(kind, SnapshotErrorKind::OutOfRange("2".to_string()));
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    for file in [SNAPSHOT, SNAPSHOT_2] {
+        let snapshot = Snapshot::from_json_file(file).unwrap();
+        assert_eq!(
+            snapshot.to_json_string().unwrap(),
+            fs::read_to_string(file).unwrap()
+        );
+    }
+
+    let snapshot = Snapshot::from_json_file(SNAPSHOT).unwrap();
+    assert_eq!(snapshot.state_block, 54994241);
+    assert_eq!(snapshot.pool.name, "Pan.V3.USD1.MERL");
+    assert_eq!(snapshot.pool.store.version, "v3");
+    assert_eq!(snapshot.pool.dex, "Pancake");
+}
+
+#[test]
+fn test_write_state_without_changes() {
+    for file in [SNAPSHOT, SNAPSHOT_2] {
+        let snapshot = Snapshot::from_json_file(file).unwrap();
+        let pool = UniswapV3Pool::try_from(&snapshot).unwrap();
+        let mut written = Snapshot::default();
+        pool.write_state(&mut written);
+        // a fresh document has no key order to keep
+        assert!(written.pool.store.ticks.keys().is_sorted());
+
+        let mut written = snapshot.clone();
+        pool.write_state(&mut written);
+        assert_eq!(
+            written.to_json_string().unwrap(),
+            fs::read_to_string(file).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_to_json_file_after_swap() {
+    let snapshot = Snapshot::from_json_file(SNAPSHOT).unwrap();
+    let mut pool = UniswapV3Pool::try_from(&snapshot).unwrap();
+    // 33% of the fee for both tokens, in PancakeSwap's packed uint32
+    pool.slot0.fee_protocol = 3300 | (3300 << 16);
+    pool.swap(SwapParams::exact_input(
+        Address::ZERO,
+        false,
+        I256::from_dec_str("1000000000000000000000000").unwrap(),
+    ))
+    .unwrap();
+    pool.ticks.insert(-10, TickInfo::default());
+
+    let path = std::env::temp_dir().join("uniswap_v3_to_json_file_after_swap.json");
+    pool.to_json_file(&path).unwrap();
+    let written = Snapshot::from_json_file(&path).unwrap();
+    let reloaded = UniswapV3Pool::try_from(&written).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(written.state_block, snapshot.state_block);
+    assert_eq!(written.pool.address, snapshot.pool.address);
+    assert_eq!(written.pool.name, snapshot.pool.name);
+    assert_eq!(written.pool.store.protocol, "V3Pool");
+    assert!(written.pool.store.ticks.keys().is_sorted());
+    assert!(written.pool.store.ticks.contains_key(&-10));
+    assert_eq!(reloaded.slot0.sqrt_price_x96, pool.slot0.sqrt_price_x96);
+    assert_eq!(reloaded.slot0.tick, 26365);
+    assert_eq!(reloaded.slot0.fee_protocol, 3300 | (3300 << 16));
+    assert_eq!(reloaded.liquidity, pool.liquidity);
+    assert_eq!(
+        reloaded.fee_growth_global1_x128,
+        pool.fee_growth_global1_x128
+    );
+    assert_eq!(reloaded.protocol_fees.token1, pool.protocol_fees.token1);
+    assert_ne!(reloaded.protocol_fees.token1, 0);
+    assert_eq!(reloaded.tick_bitmap, pool.tick_bitmap);
+    for (tick, info) in &pool.ticks {
+        let loaded = &reloaded.ticks[tick];
+        assert_eq!(loaded.liquidity_net, info.liquidity_net);
+        assert_eq!(loaded.liquidity_gross, info.liquidity_gross);
+        assert_eq!(
+            loaded.fee_growth_outside0_x128,
+            info.fee_growth_outside0_x128
+        );
+        assert_eq!(
+            loaded.fee_growth_outside1_x128,
+            info.fee_growth_outside1_x128
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use super::*;
 use alloy_primitives::Bytes;
+use std::str::FromStr;
 
 #[test]
 fn test_swap_1() {
     let mut pool = UniswapV3Pool::from_json_file(
         "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
-    );
+    )
+    .unwrap();
 
     let params = SwapParams {
         recipient: Address::from_str("0x13f4ea83d0bd40e75c8222255bc855a974568dd4").unwrap(),
@@ -32,7 +34,8 @@ fn test_swap_1() {
 fn test_swap_2() {
     let mut pool = UniswapV3Pool::from_json_file(
         "snapshots/55002250/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
-    );
+    )
+    .unwrap();
 
     let params = SwapParams {
         recipient: Address::from_str("0xf258fcd1a2c216cd3f3303bea930cca1b6350d5d").unwrap(),
@@ -53,3 +56,504 @@ fn test_swap_2() {
         "amounts are incorrect"
     );
 }
+
+#[test]
+fn test_swap_crosses_initialized_tick() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let fee_growth_outside0_x128 = pool.ticks[&22082].fee_growth_outside0_x128;
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: false,
+        amount_specified: I256::from_dec_str("1000000000000000000000000").unwrap(),
+        sqrt_price_limit_x96: tick_math::MAX_SQRT_RATIO - U256::from(1),
+        data: Bytes::new(),
+    };
+
+    let result = pool.swap(params).unwrap();
+
+    assert_eq!(
+        (result.amount0, result.amount1),
+        (
+            I256::from_dec_str("-94339598529452053658217").unwrap(),
+            I256::from_dec_str("1000000000000000000000000").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.tick, 26365);
+    assert_eq!(
+        pool.slot0.sqrt_price_x96,
+        U256::from_str("296060043358720882878363311013").unwrap()
+    );
+    // liquidity_net of tick 22082 is added when crossing it from left to right
+    assert_eq!(pool.liquidity, 1124144412144474679681414);
+    assert_eq!(
+        pool.ticks[&22082].fee_growth_outside0_x128,
+        pool.fee_growth_global0_x128 - fee_growth_outside0_x128
+    );
+}
+
+#[test]
+fn test_swap_exact_output_crosses_initialized_tick() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+
+    let params = SwapParams::exact_output(
+        Address::ZERO,
+        false,
+        I256::from_dec_str("50000000000000000000000").unwrap(),
+    );
+
+    let result = pool.swap(params).unwrap();
+
+    assert_eq!(
+        (result.amount0, result.amount1),
+        (
+            I256::from_dec_str("-50000000000000000000000").unwrap(),
+            I256::from_dec_str("460334916749556068693362").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.tick, 23615);
+    assert_eq!(pool.liquidity, 1124144412144474679681414);
+}
+
+#[test]
+fn test_swap_round_trip_across_multiple_ticks() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let liquidity = pool.liquidity;
+
+    // moves through 22082, 28148 and 30577 into the last range
+    let params = SwapParams::exact_input(
+        Address::ZERO,
+        false,
+        I256::from_dec_str("3000000000000000000000000").unwrap(),
+    );
+
+    let result = pool.swap(params).unwrap();
+
+    assert_eq!(
+        (result.amount0, result.amount1),
+        (
+            I256::from_dec_str("-144723359690175188899681").unwrap(),
+            I256::from_dec_str("3000000000000000000000000").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.tick, 278826);
+    assert_eq!(pool.liquidity, 1007868124896178342);
+
+    // and back down through the same ticks, negating liquidity_net
+    let params = SwapParams::exact_input(
+        Address::ZERO,
+        true,
+        I256::from_dec_str("1000000000000000000000000").unwrap(),
+    );
+
+    let result = pool.swap(params).unwrap();
+
+    assert_eq!(
+        (result.amount0, result.amount1),
+        (
+            I256::from_dec_str("1000000000000000000000000").unwrap(),
+            I256::from_dec_str("-4808163446908538872965730").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.tick, -5624);
+    assert_eq!(
+        pool.slot0.sqrt_price_x96,
+        U256::from_str("59809113135786428125364972922").unwrap()
+    );
+    assert_eq!(pool.liquidity, liquidity);
+}
+
+#[test]
+fn test_swap_liquidity_underflow() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    // a corrupted tick that removes more liquidity than is in range
+    pool.ticks.get_mut(&22082).unwrap().liquidity_net = -(pool.liquidity as i128) - 1;
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: false,
+        amount_specified: I256::from_dec_str("1000000000000000000000000").unwrap(),
+        sqrt_price_limit_x96: tick_math::MAX_SQRT_RATIO - U256::from(1),
+        data: Bytes::new(),
+    };
+
+    let result = pool.swap(params);
+
+    assert!(matches!(result, Err(UniswapV3MathError::LiquiditySub)));
+}
+
+#[test]
+fn test_swap_fee_growth() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let fee_growth_global0_x128 = pool.fee_growth_global0_x128;
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: false,
+        amount_specified: I256::from_dec_str("1000000000000000000000000").unwrap(),
+        sqrt_price_limit_x96: tick_math::MAX_SQRT_RATIO - U256::from(1),
+        data: Bytes::new(),
+    };
+
+    pool.swap(params).unwrap();
+
+    // only the input token accrues fees
+    assert_eq!(pool.fee_growth_global0_x128, fee_growth_global0_x128);
+    assert_eq!(
+        pool.fee_growth_global1_x128,
+        U256::from_str("31835771707472918761950470115682271").unwrap()
+    );
+    // the crossed tick records the fee growth at the time it was crossed
+    assert_eq!(
+        pool.ticks[&22082].fee_growth_outside1_x128,
+        U256::from_str("7317294859725922834340739383317650").unwrap()
+    );
+    assert_eq!(
+        (pool.protocol_fees.token0, pool.protocol_fees.token1),
+        (0, 0)
+    );
+
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/55002250/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: true,
+        amount_specified: I256::from_str("111943783213448371527").unwrap(),
+        sqrt_price_limit_x96: U256::from_str("4295128740").unwrap(),
+        data: Bytes::new(),
+    };
+
+    pool.swap(params).unwrap();
+
+    assert_eq!(
+        pool.fee_growth_global0_x128,
+        U256::from_str("8559553916886777476878671915618").unwrap()
+    );
+}
+
+#[test]
+fn test_swap_protocol_fee() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    // 1/4 of token0 fees and 1/6 of token1 fees go to the protocol, packed the Uniswap way
+    pool.metadata.dex = "Uniswap".to_string();
+    pool.slot0.fee_protocol = 0x64;
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: false,
+        amount_specified: I256::from_dec_str("1000000000000000000000000").unwrap(),
+        sqrt_price_limit_x96: tick_math::MAX_SQRT_RATIO - U256::from(1),
+        data: Bytes::new(),
+    };
+
+    pool.swap(params).unwrap();
+
+    assert_eq!(
+        (pool.protocol_fees.token0, pool.protocol_fees.token1),
+        (0, 16666666666666666660)
+    );
+    assert_eq!(
+        pool.fee_growth_global1_x128,
+        U256::from_str("26529892284200204809662419264986968").unwrap()
+    );
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: true,
+        amount_specified: I256::from_dec_str("100000000000000000000000").unwrap(),
+        sqrt_price_limit_x96: tick_math::MIN_SQRT_RATIO + U256::from(1),
+        data: Bytes::new(),
+    };
+
+    pool.swap(params).unwrap();
+
+    assert_eq!(
+        (pool.protocol_fees.token0, pool.protocol_fees.token1),
+        (2499999999999999992, 16666666666666666660)
+    );
+    assert_eq!(
+        pool.fee_growth_global0_x128,
+        U256::from_str("2445483796961990073907278867135273").unwrap()
+    );
+}
+
+#[test]
+fn test_swap_invalid_sqrt_price_limit() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let sqrt_price_x96 = pool.slot0.sqrt_price_x96;
+    let amount_specified = I256::from_dec_str("1000000000000000000").unwrap();
+
+    for (zero_for_one, sqrt_price_limit_x96) in [
+        // limit on the wrong side of the current price
+        (true, sqrt_price_x96 + U256::from(1)),
+        (false, sqrt_price_x96 - U256::from(1)),
+        // limit equal to the current price
+        (true, sqrt_price_x96),
+        (false, sqrt_price_x96),
+        // limit outside of the price range
+        (true, tick_math::MIN_SQRT_RATIO),
+        (false, tick_math::MAX_SQRT_RATIO),
+    ] {
+        let params = SwapParams {
+            recipient: Address::ZERO,
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit_x96,
+            data: Bytes::new(),
+        };
+
+        let result = pool.swap(params);
+
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::InvalidSqrtPriceLimit)
+        ));
+    }
+    assert_eq!(pool.slot0.sqrt_price_x96, sqrt_price_x96);
+}
+
+#[test]
+fn test_swap_stops_at_sqrt_price_limit() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let sqrt_price_limit_x96 = tick_math::get_sqrt_ratio_at_tick(23000).unwrap();
+
+    let params = SwapParams {
+        recipient: Address::ZERO,
+        zero_for_one: false,
+        amount_specified: I256::from_dec_str("1000000000000000000000000").unwrap(),
+        sqrt_price_limit_x96,
+        data: Bytes::new(),
+    };
+
+    let result = pool.swap(params).unwrap();
+
+    // only part of the input is used before the limit is reached
+    assert_eq!(
+        (result.amount0, result.amount1),
+        (
+            I256::from_dec_str("-39204195370875835629382").unwrap(),
+            I256::from_dec_str("349289394969978299937146").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.sqrt_price_x96, sqrt_price_limit_x96);
+    assert_eq!(pool.slot0.tick, 23000);
+}
+
+#[test]
+fn test_swap_traced() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    let params = SwapParams::exact_input(
+        Address::ZERO,
+        false,
+        I256::from_dec_str("3000000000000000000000000").unwrap(),
+    );
+
+    let untraced = pool.clone().swap(params.clone()).unwrap();
+    let result = pool.swap_traced(params).unwrap();
+
+    assert!(untraced.steps.is_none());
+    let ticks_crossed: Vec<_> = result.ticks_crossed.iter().map(|c| c.tick).collect();
+    assert_eq!(ticks_crossed, vec![22082, 28148, 30577]);
+    assert_eq!(result.ticks_crossed.len(), untraced.ticks_crossed.len());
+    assert_eq!(
+        result.fee_amount,
+        U256::from_str("300000000000000000511").unwrap()
+    );
+    assert_eq!(
+        (result.sqrt_price_x96, result.tick, result.liquidity),
+        (pool.slot0.sqrt_price_x96, pool.slot0.tick, pool.liquidity)
+    );
+
+    let steps = result.steps.unwrap();
+    let mut sqrt_price_x96 = U256::from_str("0x2cd24bf346c08355e46c874df").unwrap();
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+    let mut fee_amount = U256::ZERO;
+    for step in &steps {
+        assert_eq!(step.sqrt_price_start_x96, sqrt_price_x96);
+        sqrt_price_x96 = if step.sqrt_price_next_x96 <= result.sqrt_price_x96 {
+            step.sqrt_price_next_x96
+        } else {
+            result.sqrt_price_x96
+        };
+        amount_in += step.amount_in;
+        amount_out += step.amount_out;
+        fee_amount += step.fee_amount;
+    }
+    assert_eq!(sqrt_price_x96, result.sqrt_price_x96);
+    assert_eq!(I256::from_raw(amount_in + fee_amount), result.amount1);
+    assert_eq!(-I256::from_raw(amount_out), result.amount0);
+    assert_eq!(fee_amount, result.fee_amount);
+
+    // each crossed tick is the target of an initialized step, using the liquidity before crossing
+    let crossed: Vec<_> = steps
+        .iter()
+        .filter(|step| step.initialized)
+        .map(|step| (step.tick_next, step.liquidity))
+        .collect();
+    assert_eq!(
+        crossed,
+        vec![
+            (22082, 883709848798089040046265),
+            (28148, 1124144412144474679681414),
+            (30577, 883709848798089040046265),
+        ]
+    );
+}
+
+#[test]
+fn test_quote_does_not_modify_pool() {
+    let pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+
+    let quote = pool
+        .quote_exact_input(
+            false,
+            I256::from_dec_str("3000000000000000000000000").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        quote.amount0,
+        I256::from_dec_str("-144723359690175188899681").unwrap()
+    );
+    assert_eq!(quote.tick, 278826);
+    assert_eq!(pool.slot0.tick, 20603);
+    assert_eq!(pool.liquidity, 883709848798089040046265);
+    assert_eq!(pool.ticks[&22082].fee_growth_outside1_x128, U256::ZERO);
+
+    let quote = pool
+        .quote_exact_output(
+            false,
+            I256::from_dec_str("50000000000000000000000").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        (quote.amount0, quote.amount1),
+        (
+            I256::from_dec_str("-50000000000000000000000").unwrap(),
+            I256::from_dec_str("460334916749556068693362").unwrap()
+        ),
+        "amounts are incorrect"
+    );
+    assert_eq!(pool.slot0.tick, 20603);
+}
+
+#[test]
+fn test_quote_and_apply_matches_swap() {
+    let mut swapped = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    swapped.slot0.fee_protocol = 0x64;
+    let mut applied = swapped.clone();
+
+    for params in [
+        SwapParams::exact_input(
+            Address::ZERO,
+            false,
+            I256::from_dec_str("3000000000000000000000000").unwrap(),
+        ),
+        SwapParams::exact_output(
+            Address::ZERO,
+            true,
+            I256::from_dec_str("1000000000000000000000000").unwrap(),
+        ),
+    ] {
+        let result = swapped.swap(params.clone()).unwrap();
+        let quote = applied.quote(&params).unwrap();
+        assert_eq!(
+            (quote.amount0, quote.amount1),
+            (result.amount0, result.amount1)
+        );
+        applied.apply(&quote);
+    }
+
+    assert_eq!(applied.slot0.sqrt_price_x96, swapped.slot0.sqrt_price_x96);
+    assert_eq!(applied.slot0.tick, swapped.slot0.tick);
+    assert_eq!(applied.liquidity, swapped.liquidity);
+    assert_eq!(
+        applied.fee_growth_global0_x128,
+        swapped.fee_growth_global0_x128
+    );
+    assert_eq!(
+        applied.fee_growth_global1_x128,
+        swapped.fee_growth_global1_x128
+    );
+    assert_eq!(
+        (applied.protocol_fees.token0, applied.protocol_fees.token1),
+        (swapped.protocol_fees.token0, swapped.protocol_fees.token1)
+    );
+    for tick in [22082, 28148, 30577] {
+        assert_eq!(
+            applied.ticks[&tick].fee_growth_outside0_x128,
+            swapped.ticks[&tick].fee_growth_outside0_x128
+        );
+        assert_eq!(
+            applied.ticks[&tick].fee_growth_outside1_x128,
+            swapped.ticks[&tick].fee_growth_outside1_x128
+        );
+    }
+}
+
+#[test]
+fn test_failed_swap_does_not_modify_pool() {
+    let mut pool = UniswapV3Pool::from_json_file(
+        "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json",
+    )
+    .unwrap();
+    // crossing 22082 succeeds, crossing 28148 underflows
+    pool.ticks.get_mut(&28148).unwrap().liquidity_net = i128::MIN;
+
+    let params = SwapParams::exact_input(
+        Address::ZERO,
+        false,
+        I256::from_dec_str("3000000000000000000000000").unwrap(),
+    );
+
+    assert!(matches!(
+        pool.swap(params),
+        Err(UniswapV3MathError::LiquiditySub)
+    ));
+    assert_eq!(pool.slot0.tick, 20603);
+    assert_eq!(pool.liquidity, 883709848798089040046265);
+    assert_eq!(pool.ticks[&22082].fee_growth_outside0_x128, U256::ZERO);
+}
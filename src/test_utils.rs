@@ -0,0 +1,28 @@
+use alloy_primitives::{Address, I256, U256};
+use std::str::FromStr;
+
+pub const SNAPSHOT: &str =
+    "snapshots/54994241/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json";
+/// The same pool at a later block.
+pub const SNAPSHOT_2: &str =
+    "snapshots/55002250/Pan.V3.USD1.MERL.0xb604D4E46509FE1c1ef70Ab4a4941d12a49Dbd76.json";
+/// The logs the pool emitted after `SNAPSHOT`, up to and including the block of `SNAPSHOT_2`, as
+/// returned by `eth_getLogs` one per line.
+pub const LOGS: &str = "logs/Pan.V3.USD1.MERL.54994242-55002250.jsonl";
+
+/// The account that swaps and provides liquidity in the tests.
+pub fn owner() -> Address {
+    address("0x13f4ea83d0bd40e75c8222255bc855a974568dd4")
+}
+
+pub fn address(value: &str) -> Address {
+    Address::from_str(value).unwrap()
+}
+
+pub fn i256(value: &str) -> I256 {
+    I256::from_str(value).unwrap()
+}
+
+pub fn u256(value: &str) -> U256 {
+    U256::from_str(value).unwrap()
+}